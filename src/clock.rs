@@ -0,0 +1,56 @@
+use chrono::{DateTime, Duration, TimeZone, Utc};
+use std::sync::Mutex;
+
+/// A source of the current time, for [`Space::status`](super::Space::status)
+/// and [`Space::next_status_change`](super::Space::next_status_change)
+pub trait Clock<Tz: TimeZone> {
+	fn now(&self) -> DateTime<Tz>;
+}
+
+/// The system's clock, giving the current time in a time zone
+#[derive(Clone, Debug)]
+pub struct SystemClock<Tz: TimeZone> {
+	timezone: Tz,
+}
+
+impl<Tz: TimeZone> SystemClock<Tz> {
+	pub fn new(timezone: Tz) -> Self {
+		Self { timezone }
+	}
+}
+
+impl<Tz: TimeZone> Clock<Tz> for SystemClock<Tz> {
+	fn now(&self) -> DateTime<Tz> {
+		Utc::now().with_timezone(&self.timezone)
+	}
+}
+
+/// A clock which stays at a given time until it is set or advanced, for
+/// testing
+#[derive(Debug)]
+pub struct ManualClock<Tz: TimeZone> {
+	now: Mutex<DateTime<Tz>>,
+}
+
+impl<Tz: TimeZone> ManualClock<Tz> {
+	pub fn new(now: DateTime<Tz>) -> Self {
+		Self {
+			now: Mutex::new(now),
+		}
+	}
+
+	pub fn set(&self, now: DateTime<Tz>) {
+		*self.now.lock().unwrap() = now;
+	}
+
+	pub fn advance(&self, duration: Duration) {
+		let mut now = self.now.lock().unwrap();
+		*now = now.to_owned() + duration;
+	}
+}
+
+impl<Tz: TimeZone> Clock<Tz> for ManualClock<Tz> {
+	fn now(&self) -> DateTime<Tz> {
+		self.now.lock().unwrap().to_owned()
+	}
+}
@@ -0,0 +1,268 @@
+use super::{Exception, Part, Reason, Rule, RuleError, Space, Status, StatusError, Window};
+use chrono::{DateTime, TimeZone};
+use std::sync::Arc;
+
+/// A [`Part`] with the windows of time during which it applies precomputed
+#[derive(Debug)]
+struct CompiledPart<'space, Tz: TimeZone> {
+	part: &'space Arc<Part<Tz>>,
+	windows: Vec<Window<Tz>>,
+}
+
+impl<'space, Tz: TimeZone> CompiledPart<'space, Tz> {
+	/// Compile a part, keeping only the windows for which `keep` is true of the
+	/// opening, if there is one
+	fn new(
+		part: &'space Arc<Part<Tz>>,
+		from: &DateTime<Tz>,
+		to: &DateTime<Tz>,
+		keep: impl Fn(&DateTime<Tz>) -> Result<bool, StatusError>,
+		error: impl Fn(RuleError) -> StatusError,
+	) -> Result<Self, StatusError> {
+		let mut windows = Vec::new();
+
+		for window in part.windows(from, to).map_err(error)? {
+			let kept = match window.since.as_ref() {
+				Some(opened) => keep(opened)?,
+				None => true,
+			};
+
+			if kept {
+				windows.push(window);
+			}
+		}
+
+		Ok(Self { part, windows })
+	}
+
+	fn window_at(&self, time: &DateTime<Tz>) -> Option<&Window<Tz>> {
+		Window::find(&self.windows, time)
+	}
+}
+
+/// An [`Exception`] with the windows of time during which it applies, and
+/// those of its parts, precomputed
+#[derive(Debug)]
+struct CompiledException<'space, Tz: TimeZone> {
+	exception: &'space Exception<Tz>,
+	windows: Vec<Window<Tz>>,
+	/// The exception's parts, keeping only the windows which opened while the
+	/// exception applied
+	parts: Vec<CompiledPart<'space, Tz>>,
+}
+
+impl<'space, Tz: TimeZone> CompiledException<'space, Tz> {
+	fn window_at(&self, time: &DateTime<Tz>) -> Option<&Window<Tz>> {
+		Window::find(&self.windows, time)
+	}
+
+	/// Find the first of the exception's parts which is open at the given time,
+	/// as [`Exception::replacement_at`] does
+	fn replacement_at(&self, time: &DateTime<Tz>) -> Option<&'space Arc<Part<Tz>>> {
+		self
+			.parts
+			.iter()
+			.find(|compiled| match compiled.window_at(time) {
+				Some(window) => window.since.is_some() || self.window_at(time).is_some(),
+				None => false,
+			})
+			.map(|compiled| compiled.part)
+	}
+
+	/// Compute the status which the exception gives the space at the given
+	/// time, as [`Exception::effect_at`] does
+	fn effect_at(&self, time: &DateTime<Tz>) -> Option<Status<Tz>> {
+		match self.exception.effect() {
+			Some(effect) => Some(effect.clone()),
+			None => self
+				.replacement_at(time)
+				.map(|part| Status::Open(Reason::Part(Some(Arc::clone(part))))),
+		}
+	}
+}
+
+/// A [`Schedule`](super::Schedule) with its parts and exceptions precomputed
+/// over the part of a window of time during which it is in effect
+#[derive(Debug)]
+pub(crate) struct CompiledSchedule<'space, Tz: TimeZone> {
+	/// The position of the schedule in its space
+	index: usize,
+	/// The schedule's parts, leaving out the windows which opened while an
+	/// exception replaced them
+	parts: Vec<CompiledPart<'space, Tz>>,
+	exceptions: Vec<CompiledException<'space, Tz>>,
+}
+
+impl<'space, Tz: TimeZone> CompiledSchedule<'space, Tz> {
+	pub(crate) fn new(
+		space: &'space Space<Tz>,
+		index: usize,
+		from: &DateTime<Tz>,
+		to: &DateTime<Tz>,
+	) -> Result<Self, StatusError> {
+		let schedule = &space.schedules()[index];
+
+		// The schedule is only chosen while it is in effect, so its rules are never
+		// evaluated outside of that, and may not be evaluable there.
+		let from = match schedule.effective() {
+			Some(effective) if effective > from => effective,
+			_ => from,
+		};
+		let to = match schedule.expires() {
+			Some(expires) if expires < to => expires,
+			_ => to,
+		};
+
+		if from >= to {
+			return Ok(Self {
+				index,
+				parts: Vec::new(),
+				exceptions: Vec::new(),
+			});
+		}
+
+		let parts = schedule
+			.parts()
+			.iter()
+			.enumerate()
+			.map(|(position, part)| {
+				CompiledPart::new(
+					part,
+					from,
+					to,
+					|opened| space.is_replaced(index, opened).map(|replaced| !replaced),
+					|error| space.error(index, Rule::Part(position), error),
+				)
+			})
+			.collect::<Result<_, StatusError>>()?;
+
+		let exceptions = schedule
+			.exceptions()
+			.iter()
+			.enumerate()
+			.map(|(position, exception)| {
+				let error = |error| space.error(index, Rule::Exception(position), error);
+
+				let parts = exception
+					.parts()
+					.iter()
+					.map(|part| {
+						CompiledPart::new(
+							part,
+							from,
+							to,
+							|opened| exception.applies_at(opened).map_err(error),
+							error,
+						)
+					})
+					.collect::<Result<_, StatusError>>()?;
+
+				Ok(CompiledException {
+					exception,
+					windows: exception.windows(from, to).map_err(error)?,
+					parts,
+				})
+			})
+			.collect::<Result<_, StatusError>>()?;
+
+		Ok(Self {
+			index,
+			parts,
+			exceptions,
+		})
+	}
+
+	/// Iterate over the positions of the schedule's parts which apply at the
+	/// given time and aren't replaced by an exception, in order
+	fn parts_at<'a>(&'a self, time: &'a DateTime<Tz>) -> impl Iterator<Item = usize> + 'a {
+		let replaced = self
+			.exceptions
+			.iter()
+			.any(|compiled| compiled.exception.is_replacement() && compiled.window_at(time).is_some());
+
+		(self.parts.iter().enumerate())
+			.filter(move |(_, compiled)| match compiled.window_at(time) {
+				// A part which has no opening is replaced while the exception applies.
+				Some(window) => window.since.is_some() || !replaced,
+				None => false,
+			})
+			.map(|(position, _)| position)
+	}
+}
+
+/// A [`Space`] with the parts of its schedules precomputed over a window of
+/// time, created with [`Space::compile`]
+///
+/// Status queries within the window look up each part and exception with a
+/// binary search and don't allocate unless an exception applies. Queries
+/// outside of the window fall back to [`Space::status_at`]. Either way, the
+/// results are the same as those of [`Space::status_at`], except that an error
+/// in a part or exception is reported when the space is compiled.
+#[derive(Debug)]
+pub struct CompiledSpace<'space, Tz: TimeZone> {
+	space: &'space Space<Tz>,
+	from: DateTime<Tz>,
+	to: DateTime<Tz>,
+	schedules: Vec<CompiledSchedule<'space, Tz>>,
+}
+
+impl<'space, Tz: TimeZone> CompiledSpace<'space, Tz> {
+	pub fn new(
+		space: &'space Space<Tz>,
+		from: &DateTime<Tz>,
+		to: &DateTime<Tz>,
+	) -> Result<Self, StatusError> {
+		let schedules = (0..space.schedules().len())
+			.map(|index| CompiledSchedule::new(space, index, from, to))
+			.collect::<Result<_, StatusError>>()?;
+
+		Ok(Self {
+			space,
+			from: from.to_owned(),
+			to: to.to_owned(),
+			schedules,
+		})
+	}
+
+	/// Compute the status of the space at the given time
+	pub fn status_at(&self, time: &DateTime<Tz>) -> Result<Status<Tz>, StatusError> {
+		if time < &self.from || time >= &self.to {
+			return self.space.status_at(time);
+		}
+
+		let compiled = match self.space.schedule_at(time) {
+			Some((index, _)) => &self.schedules[index],
+			None => return Ok(Status::Closed(Reason::Part(None))),
+		};
+
+		let applicable: Vec<(usize, Option<&DateTime<Tz>>)> = (compiled.exceptions.iter().enumerate())
+			.filter_map(|(position, exception)| {
+				(exception.window_at(time)).map(|window| (position, window.since.as_ref()))
+			})
+			.collect();
+
+		let resolution = self
+			.space
+			.resolve_exceptions(compiled.index, &applicable, |position| {
+				Ok(compiled.exceptions[position].effect_at(time))
+			})?;
+
+		if let Some((_, effect)) = resolution.winner {
+			return Ok(effect);
+		}
+
+		let replacement = (compiled.exceptions.iter().enumerate())
+			.filter(|(_, exception)| exception.exception.is_replacement())
+			.find_map(|(position, exception)| {
+				(exception.replacement_at(time)).map(|part| (position, part))
+			});
+		let part_status = self.space.part_status(
+			compiled.index,
+			replacement,
+			compiled.parts_at(time),
+			&applicable,
+		);
+
+		Ok(part_status.status)
+	}
+}
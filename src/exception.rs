@@ -1,37 +1,124 @@
-use super::{Specifier, Status};
+use super::{Part, Reason, RuleError, RuleTrace, Specifier, Status, Window};
 use chrono::{DateTime, TimeZone};
+use std::sync::Arc;
+
+/// How to choose between exceptions of the same priority which apply at the
+/// same time, set with [`Space::exception_precedence`](super::Space::exception_precedence)
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub enum ExceptionPrecedence {
+	/// The exception added to the schedule first wins
+	#[default]
+	FirstAdded,
+	/// The exception which took effect most recently wins
+	MostRecent,
+	/// An exception which closes the space wins over one which opens it
+	ClosuresFirst,
+}
 
 #[allow(dead_code)]
 #[derive(Debug)]
-pub struct Exception<'schedule, Tz: TimeZone> {
-	effect: Option<Status<'schedule, Tz>>,
+pub struct Exception<Tz: TimeZone> {
+	effect: Option<Status<Tz>>,
 	effective: Option<Specifier<Tz>>,
 	expires: Option<Specifier<Tz>>,
+	priority: i32,
+	parts: Vec<Arc<Part<Tz>>>,
+	targets: Vec<String>,
 }
 
-impl<'schedule, Tz: TimeZone> Default for Exception<'schedule, Tz> {
+impl<Tz: TimeZone> Default for Exception<Tz> {
 	fn default() -> Self {
 		Self {
 			effect: None,
 			effective: None,
 			expires: None,
+			priority: 0,
+			parts: Vec::new(),
+			targets: Vec::new(),
 		}
 	}
 }
 
-impl<'schedule, Tz: TimeZone> Exception<'schedule, Tz> {
+impl<Tz: TimeZone> Exception<Tz> {
 	pub fn new() -> Self {
 		Self::default()
 	}
 
-	pub fn effect_mut(&mut self) -> &mut Option<Status<'schedule, Tz>> {
+	/// Create an exception which applies once, from `from` until `until`, such
+	/// as a closure for a holiday
+	pub fn during(from: DateTime<Tz>, until: DateTime<Tz>) -> Self {
+		Self::new()
+			.effective(Specifier::Exact(from))
+			.expires(Specifier::Exact(until))
+	}
+
+	pub fn effect_mut(&mut self) -> &mut Option<Status<Tz>> {
 		&mut self.effect
 	}
 
-	pub fn effect(&self) -> &Option<Status<'schedule, Tz>> {
+	pub fn effect(&self) -> &Option<Status<Tz>> {
 		&self.effect
 	}
 
+	/// The priority of the exception over others which apply at the same time,
+	/// which is 0 unless set; the exception with the highest priority wins
+	pub fn priority(&self) -> i32 {
+		self.priority
+	}
+
+	pub fn priority_mut(&mut self) -> &mut i32 {
+		&mut self.priority
+	}
+
+	/// The parts which replace those of the schedule while the exception
+	/// applies, such as extended hours during finals
+	pub fn parts(&self) -> &Vec<Arc<Part<Tz>>> {
+		&self.parts
+	}
+
+	pub fn parts_mut(&mut self) -> &mut Vec<Arc<Part<Tz>>> {
+		&mut self.parts
+	}
+
+	pub fn part(mut self, part: Part<Tz>) -> Self {
+		self.parts.push(Arc::new(part));
+		self
+	}
+
+	/// The labels of the parts which the exception targets, set with [`Exception::target`]
+	pub fn targets(&self) -> &Vec<String> {
+		&self.targets
+	}
+
+	pub fn targets_mut(&mut self) -> &mut Vec<String> {
+		&mut self.targets
+	}
+
+	/// Target the parts with the given label, so that while the exception
+	/// applies it opens or closes only those parts and leaves the rest of the
+	/// space alone
+	///
+	/// The targeted parts are open if the exception's effect opens the space,
+	/// even outside their own hours, and closed if it closes the space or the
+	/// exception has no effect. Exceptions which target the same part are
+	/// chosen between by priority and the space's [`ExceptionPrecedence`]. If a
+	/// part which would otherwise be open is closed, but another is open, the
+	/// space stays open with [`Reason::Reduced`]; if none is open, it is closed
+	/// with the effect of the exception which closed it.
+	pub fn target(mut self, label: &str) -> Self {
+		self.targets.push(label.to_string());
+		self
+	}
+
+	/// Determine whether the exception targets the given part, by one of its
+	/// labels
+	pub fn targets_part(&self, part: &Part<Tz>) -> bool {
+		part
+			.labels()
+			.iter()
+			.any(|label| self.targets.contains(label))
+	}
+
 	pub fn effective(mut self, effective: Specifier<Tz>) -> Self {
 		self.effective = Some(effective);
 		self
@@ -42,17 +129,172 @@ impl<'schedule, Tz: TimeZone> Exception<'schedule, Tz> {
 		self
 	}
 
-	pub fn applies_at(&self, time: &DateTime<Tz>) -> bool {
+	/// Find the earliest time strictly after `time` at which this exception
+	/// takes effect or expires, or one of its parts opens or closes
+	pub fn next_transition_after(&self, time: &DateTime<Tz>) -> Option<DateTime<Tz>> {
+		self
+			.effective
+			.iter()
+			.chain(self.expires.iter())
+			.filter_map(|specifier| specifier.next_after(time))
+			.chain(
+				self
+					.parts
+					.iter()
+					.filter_map(|part| part.next_transition_after(time)),
+			)
+			.min()
+	}
+
+	/// Determine whether the exception replaces the parts of its schedule with
+	/// its own, which it does if it has parts but no effect or targets
+	pub fn is_replacement(&self) -> bool {
+		self.effect.is_none() && !self.parts.is_empty() && self.targets.is_empty()
+	}
+
+	/// Find the first of the exception's parts which is open at the given time,
+	/// having opened while the exception applied
+	///
+	/// A part only counts from an opening within the exception's window, but
+	/// then stays open until its own close, even if the window has ended by then.
+	/// So with hours from 07:00 until 02:00 during finals, the space stays open
+	/// until 02:00 after the last day of finals, but not after the day before the
+	/// first. A part which has no opening counts while the exception applies.
+	pub fn replacement_at(&self, time: &DateTime<Tz>) -> Result<Option<&Arc<Part<Tz>>>, RuleError> {
+		for part in self.parts.iter() {
+			let trace = part.trace_at(time)?;
+
+			if trace.applies && self.applies_at(trace.start.as_ref().unwrap_or(time))? {
+				return Ok(Some(part));
+			}
+		}
+
+		Ok(None)
+	}
+
+	/// Compute the status which the exception gives the space at the given
+	/// time, assuming that it applies
+	///
+	/// This is the exception's effect if it has one. Otherwise, if it has parts,
+	/// the space is open while one of them is, as found by
+	/// [`Exception::replacement_at`]. While none of them is, the exception has
+	/// no say in the status, and neither does an exception with no effect or
+	/// parts, so this is `None`.
+	pub fn effect_at(&self, time: &DateTime<Tz>) -> Result<Option<Status<Tz>>, RuleError> {
+		if let Some(effect) = self.effect.as_ref() {
+			return Ok(Some(effect.clone()));
+		}
+
+		Ok(
+			self
+				.replacement_at(time)?
+				.map(|part| Status::Open(Reason::Part(Some(Arc::clone(part))))),
+		)
+	}
+
+	pub fn applies_at(&self, time: &DateTime<Tz>) -> Result<bool, RuleError> {
+		self.trace_at(time).map(|trace| trace.applies)
+	}
+
+	/// Compute the windows of time between `from` and `to` during which the
+	/// exception applies
+	///
+	/// The windows are in order and don't overlap, and are clipped to `from` and
+	/// `to`; together they cover exactly the times at which
+	/// [`Exception::applies_at`] is true. Unlike `applies_at`, this fails if the
+	/// exception takes effect without expiring afterwards at any time in the
+	/// window, not just at a given time.
+	pub fn windows(
+		&self,
+		from: &DateTime<Tz>,
+		to: &DateTime<Tz>,
+	) -> Result<Vec<Window<Tz>>, RuleError> {
+		match (self.effective.as_ref(), self.expires.as_ref()) {
+			(Some(effective), expires) => Window::between(effective, from, to, |since| match expires {
+				Some(expires) => expires
+					.next_after(since)
+					.map(Some)
+					.ok_or(RuleError::NoInstance("expires")),
+				None => Ok(None),
+			}),
+			(None, Some(expires)) => {
+				// The exception applies until its last expiry, if that's in the window.
+				let until = match expires.next_after(to) {
+					Some(_) => Some(to.to_owned()),
+					None => expires.last_at_or_before(to),
+				};
+
+				Ok(
+					(until.into_iter())
+						.filter(|until| until > from)
+						.map(|until| Window {
+							since: None,
+							start: from.to_owned(),
+							end: until,
+						})
+						.collect(),
+				)
+			}
+			(None, None) => Ok(vec![Window {
+				since: None,
+				start: from.to_owned(),
+				end: to.to_owned(),
+			}]),
+		}
+	}
+
+	/// Determine whether the exception applies at the given time, as
+	/// [`Exception::applies_at`] does, along with the `effective` and `expires`
+	/// times which decided it
+	///
+	/// An exception applies from the last time at or before `time` that it took
+	/// effect until the next time after that it expires. Without an `expires`
+	/// specifier, it applies indefinitely once it has taken effect; without an
+	/// `effective` specifier, it applies until the next time it expires; and
+	/// without either, it always applies.
+	pub fn trace_at(&self, time: &DateTime<Tz>) -> Result<RuleTrace<Tz>, RuleError> {
 		match (self.effective.as_ref(), self.expires.as_ref()) {
-			(Some(open), Some(close)) => {
-				let open = open.instances(time).next().unwrap();
-				let close = close.instances(time).next().unwrap();
+			(Some(effective), expires) => {
+				let since = match effective.last_at_or_before(time) {
+					Some(since) => since,
+					None => {
+						return Ok(RuleTrace {
+							applies: false,
+							start: None,
+							end: None,
+						})
+					}
+				};
+
+				let until = match expires {
+					Some(expires) => Some(
+						expires
+							.next_after(&since)
+							.ok_or(RuleError::NoInstance("expires"))?,
+					),
+					None => None,
+				};
+
+				Ok(RuleTrace {
+					applies: until.as_ref().is_none_or(|until| time < until),
+					start: Some(since),
+					end: until,
+				})
+			}
+			(None, Some(expires)) => {
+				let until = expires.next_after(time);
 
-				(open..close).contains(time)
+				Ok(RuleTrace {
+					applies: until.is_some(),
+					start: None,
+					end: until,
+				})
 			}
-			(None, Some(_)) => true,
-			(Some(_), None) => true,
-			(None, None) => true,
+			(None, None) => Ok(RuleTrace {
+				applies: true,
+				start: None,
+				end: None,
+			}),
 		}
 	}
 }
@@ -0,0 +1,139 @@
+use super::{Reason, Rule, Status};
+use chrono::{DateTime, TimeZone};
+
+/// Why a schedule was or wasn't considered at a time
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Activity {
+	Active,
+	/// The time is before the schedule's `effective` time
+	NotYetEffective,
+	/// The time is at or after the schedule's `expires` time
+	Expired,
+	/// The schedule is in effect, but another one takes precedence over it
+	Superseded,
+}
+
+/// How a part or exception was evaluated at a time
+///
+/// `start` and `end` are the instances of its specifiers which decided
+/// whether it applies: the opening and following close of a part, or the
+/// `effective` and `expires` times of an exception. They are `None` if the
+/// part or exception doesn't have the corresponding specifier, or hasn't
+/// started yet; a part has neither unless it has both.
+#[derive(Clone, Debug, PartialEq)]
+pub struct RuleTrace<Tz: TimeZone> {
+	pub applies: bool,
+	pub start: Option<DateTime<Tz>>,
+	pub end: Option<DateTime<Tz>>,
+}
+
+/// How a schedule was evaluated at a time
+///
+/// Only the parts and exceptions of the schedule which takes precedence are
+/// evaluated, in the same order as in the schedule; for any other schedule,
+/// they are empty.
+#[derive(Clone, Debug, PartialEq)]
+pub struct ScheduleTrace<Tz: TimeZone> {
+	pub activity: Activity,
+	pub parts: Vec<RuleTrace<Tz>>,
+	pub exceptions: Vec<RuleTrace<Tz>>,
+}
+
+/// A trace of how the status of a [`Space`](super::Space) was computed,
+/// created with [`Space::explain_at`](super::Space::explain_at)
+#[derive(Clone, Debug, PartialEq)]
+pub struct Explanation<Tz: TimeZone> {
+	pub space: String,
+	pub time: DateTime<Tz>,
+	/// Every schedule of the space, in order
+	pub schedules: Vec<ScheduleTrace<Tz>>,
+	/// The schedule which took precedence, or `None` if none is in effect
+	pub schedule: Option<usize>,
+	/// The schedule and part or exception which decided the status, or `None`
+	/// if the space is closed because nothing applies
+	pub decided_by: Option<(usize, Rule)>,
+	/// Exceptions which applied, but were overridden by the one which decided
+	/// the status even though their effects disagree with it
+	pub conflicts: Vec<(usize, Rule)>,
+	pub status: Status<Tz>,
+}
+
+/// A stretch of time from `start` (inclusive) until `end` (exclusive) during
+/// which exceptions which apply disagree about the status of a space, found
+/// with [`Space::conflicts`](super::Space::conflicts)
+#[derive(Clone, Debug, PartialEq)]
+pub struct Conflict<Tz: TimeZone> {
+	pub start: DateTime<Tz>,
+	pub end: DateTime<Tz>,
+	/// The schedule and part or exception which decided the status
+	pub decided_by: Option<(usize, Rule)>,
+	/// The exceptions which it overrode, as in [`Explanation::conflicts`]
+	pub overridden: Vec<(usize, Rule)>,
+}
+
+impl<Tz: TimeZone> core::fmt::Display for Explanation<Tz>
+where
+	Tz::Offset: core::fmt::Display,
+{
+	fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+		let (state, reason) = match &self.status {
+			Status::Open(reason) => ("open", reason),
+			Status::Closed(reason) => ("closed", reason),
+		};
+
+		write!(f, "{:?} is {} at {}", self.space, state, self.time)?;
+		match reason {
+			Reason::Exception(Some(message)) => write!(f, ": {}", message)?,
+			Reason::Reduced { .. } => write!(f, " with reduced service")?,
+			_ => {}
+		}
+		writeln!(f)?;
+
+		for (index, schedule) in self.schedules.iter().enumerate() {
+			match schedule.activity {
+				Activity::Active => writeln!(f, "  schedule {} is active", index)?,
+				Activity::NotYetEffective => writeln!(f, "  schedule {} is not yet effective", index)?,
+				Activity::Expired => writeln!(f, "  schedule {} has expired", index)?,
+				Activity::Superseded => writeln!(f, "  schedule {} is superseded", index)?,
+			}
+
+			let rules = (schedule.parts.iter().enumerate())
+				.map(|(position, trace)| (Rule::Part(position), trace))
+				.chain(
+					(schedule.exceptions.iter().enumerate())
+						.map(|(position, trace)| (Rule::Exception(position), trace)),
+				);
+
+			for (rule, trace) in rules {
+				let applies = if trace.applies {
+					"applies"
+				} else {
+					"doesn't apply"
+				};
+				write!(f, "    {} {}", rule, applies)?;
+				match (&trace.start, &trace.end) {
+					(Some(start), Some(end)) => write!(f, " (from {} until {})", start, end)?,
+					(Some(start), None) => write!(f, " (from {})", start)?,
+					(None, Some(end)) => write!(f, " (until {})", end)?,
+					(None, None) => {}
+				}
+				writeln!(f)?;
+			}
+		}
+
+		match self.decided_by {
+			Some((schedule, rule)) => write!(f, "decided by schedule {}, {}", schedule, rule)?,
+			None => write!(f, "no part or exception applies")?,
+		}
+
+		for (schedule, rule) in self.conflicts.iter() {
+			write!(
+				f,
+				"\noverriding conflicting schedule {}, {}",
+				schedule, rule
+			)?;
+		}
+
+		Ok(())
+	}
+}
@@ -0,0 +1,107 @@
+use super::{Space, Status, StatusChange, StatusError};
+use chrono::{DateTime, TimeZone};
+
+/// A sorted table of every change in the status of a [`Space`] within a window
+/// of time, created with [`Space::index`]
+///
+/// Status queries within the window are binary searches over the table, and
+/// queries outside of it fall back to computing the status directly.
+#[derive(Debug)]
+pub struct StatusIndex<'space, Tz: TimeZone> {
+	space: &'space Space<Tz>,
+	from: DateTime<Tz>,
+	to: DateTime<Tz>,
+	/// The start of each stretch of time with the same status, in order; each
+	/// stretch ends where the next one starts, and the last ends at `to`.
+	entries: Vec<(DateTime<Tz>, Status<Tz>)>,
+}
+
+impl<'space, Tz: TimeZone> StatusIndex<'space, Tz>
+where
+	Status<Tz>: PartialEq,
+{
+	pub fn new(
+		space: &'space Space<Tz>,
+		from: &DateTime<Tz>,
+		to: &DateTime<Tz>,
+	) -> Result<Self, StatusError> {
+		let entries = space
+			.timeline(from, to)
+			.map(|stretch| stretch.map(|(start, _, status)| (start, status)))
+			.collect::<Result<_, StatusError>>()?;
+
+		Ok(Self {
+			space,
+			from: from.to_owned(),
+			to: to.to_owned(),
+			entries,
+		})
+	}
+
+	fn contains(&self, time: &DateTime<Tz>) -> bool {
+		&self.from <= time && time < &self.to
+	}
+
+	/// Find the index of the entry covering the given time, which must be within
+	/// the window
+	fn entry_at(&self, time: &DateTime<Tz>) -> usize {
+		self.entries.partition_point(|(start, _)| start <= time) - 1
+	}
+
+	/// Compute the status of the space at the given time
+	pub fn status_at(&self, time: &DateTime<Tz>) -> Result<Status<Tz>, StatusError> {
+		if !self.contains(time) {
+			return self.space.status_at(time);
+		}
+
+		Ok(self.entries[self.entry_at(time)].1.clone())
+	}
+
+	/// Compute the next time after the given time at which the space opens or
+	/// closes, as [`Space::next_status_change_at`] does
+	pub fn next_status_change_at(
+		&self,
+		time: &DateTime<Tz>,
+	) -> Result<Option<StatusChange<Tz>>, StatusError> {
+		if !self.contains(time) {
+			return self.space.next_status_change_at(time);
+		}
+
+		let index = self.entry_at(time);
+		let currently_open = self.entries[index].1.is_open();
+
+		let change = self.entries[index + 1..]
+			.iter()
+			.find(|(_, status)| status.is_open() != currently_open);
+
+		match change {
+			Some((start, Status::Open(reason))) => Ok(Some(StatusChange::Opening(
+				start.to_owned(),
+				reason.clone(),
+			))),
+			Some((start, Status::Closed(reason))) => Ok(Some(StatusChange::Closing(
+				start.to_owned(),
+				reason.clone(),
+			))),
+			// The change, if any, is past the end of the window.
+			None => self.space.next_status_change_at(time),
+		}
+	}
+
+	/// Iterate over the stretches of time in the window during which the status
+	/// of the space stays the same, as [`Space::timeline`] does
+	pub fn iter(&self) -> impl Iterator<Item = (&DateTime<Tz>, &DateTime<Tz>, &Status<Tz>)> + '_ {
+		let ends = self
+			.entries
+			.iter()
+			.skip(1)
+			.map(|(start, _)| start)
+			.chain(core::iter::once(&self.to));
+
+		self
+			.entries
+			.iter()
+			.zip(ends)
+			.map(|((start, status), end)| (start, end, status))
+	}
+}
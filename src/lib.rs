@@ -1,18 +1,28 @@
+mod clock;
+mod compiled;
 mod exception;
+mod explanation;
+mod index;
 mod part;
 pub mod pdf;
 mod schedule;
 mod space;
 mod specifier;
 mod status;
+mod timeline;
 
+pub use clock::*;
+pub use compiled::*;
 pub use exception::*;
+pub use explanation::*;
+pub use index::*;
 pub use part::*;
 pub use pdf::*;
 pub use schedule::*;
 pub use space::*;
 pub use specifier::*;
 pub use status::*;
+pub use timeline::*;
 
 #[cfg(test)]
 mod tests {}
@@ -12,7 +12,9 @@ use sked::pdf::Pdf;
 
 fn main() {
 	#[cfg(feature = "simple_logger")]
-	simple_logger::init().expect("couldn't init simple_logger");
+	simple_logger::SimpleLogger::new()
+		.init()
+		.expect("couldn't init simple_logger");
 
 	let file = env::args().nth(1).unwrap();
 	println!("Loading from {}...", file);
@@ -23,7 +25,7 @@ fn main() {
 
 	let unvisited_object_ids: BTreeSet<ObjectId> = doc.objects.keys().cloned().collect();
 
-	let pdf: Pdf = Pdf::new().version(&doc.version);
+	let _pdf: Pdf = Pdf::new().version(&doc.version);
 
 	let walked: BTreeSet<ObjectId> = doc
 		.traverse_objects(|object: &mut Object| {
@@ -1,5 +1,78 @@
-use super::Specifier;
-use chrono::{DateTime, TimeZone};
+use super::{gap_before, RuleError, RuleTrace, Specifier, WeekdaySet};
+use chrono::{DateTime, NaiveTime, TimeZone};
+
+/// A stretch of time during which a part or exception applies, from `start`
+/// (inclusive) until `end` (exclusive), along with the time at which it most
+/// recently opened or took effect, if it has such a time
+///
+/// `since` is the same as the `start` of its [`RuleTrace`] at any time within
+/// the window, and may be earlier than `start` if the window was clipped.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Window<Tz: TimeZone> {
+	pub since: Option<DateTime<Tz>>,
+	pub start: DateTime<Tz>,
+	pub end: DateTime<Tz>,
+}
+
+impl<Tz: TimeZone> Window<Tz> {
+	/// Find the window which contains `time` in a list of windows which are in
+	/// order and don't overlap
+	pub fn find<'a>(windows: &'a [Window<Tz>], time: &DateTime<Tz>) -> Option<&'a Window<Tz>> {
+		let after = windows.partition_point(|window| &window.start <= time);
+
+		Some(&windows[after.checked_sub(1)?]).filter(|window| time < &window.end)
+	}
+
+	/// Compute the windows between `from` and `to` which begin at instances of
+	/// `opens`, each lasting until the time given by `end_of` for its opening,
+	/// or indefinitely if that is `None`, but no later than the next opening
+	///
+	/// The windows are in order, don't overlap and are clipped to `from` and
+	/// `to`; an opening at or before `from` is included if it is the last one.
+	pub(crate) fn between(
+		opens: &Specifier<Tz>,
+		from: &DateTime<Tz>,
+		to: &DateTime<Tz>,
+		end_of: impl Fn(&DateTime<Tz>) -> Result<Option<DateTime<Tz>>, RuleError>,
+	) -> Result<Vec<Window<Tz>>, RuleError> {
+		let mut openings = opens
+			.last_at_or_before(from)
+			.into_iter()
+			.chain(
+				opens
+					.instances(from)
+					.skip_while(|instance| instance <= from),
+			)
+			.take_while(|instance| instance < to)
+			.peekable();
+
+		let mut windows: Vec<Window<Tz>> = Vec::new();
+
+		while let Some(opened) = openings.next() {
+			let end = (end_of(&opened)?.into_iter())
+				.chain(openings.peek().cloned())
+				.fold(
+					to.to_owned(),
+					|end, time| if time < end { time } else { end },
+				);
+			let start = if &opened < from {
+				from.to_owned()
+			} else {
+				opened.clone()
+			};
+
+			if end > start {
+				windows.push(Window {
+					since: Some(opened),
+					start,
+					end,
+				});
+			}
+		}
+
+		Ok(windows)
+	}
+}
 
 #[allow(dead_code)]
 #[derive(Clone, Debug, PartialEq)]
@@ -7,6 +80,7 @@ pub struct Part<Tz: TimeZone> {
 	open: Option<Specifier<Tz>>,
 	close: Option<Specifier<Tz>>,
 	notes: Vec<String>,
+	labels: Vec<String>,
 }
 
 impl<Tz: TimeZone> Default for Part<Tz> {
@@ -15,6 +89,7 @@ impl<Tz: TimeZone> Default for Part<Tz> {
 			open: None,
 			close: None,
 			notes: Vec::new(),
+			labels: Vec::new(),
 		}
 	}
 }
@@ -34,22 +109,157 @@ impl<Tz: TimeZone> Part<Tz> {
 		self
 	}
 
+	/// Open and close at the given times on each of a set of days
+	///
+	/// If `close` isn't after `open`, the part is taken to close on the
+	/// following day, e.g. Friday 22:00 until Saturday 02:00.
+	pub fn on_days(self, days: WeekdaySet, open: NaiveTime, close: NaiveTime) -> Self {
+		let close_days = if close > open { days } else { days.succ() };
+
+		self
+			.open(Specifier::Weekdays { days, time: open })
+			.close(Specifier::Weekdays {
+				days: close_days,
+				time: close,
+			})
+	}
+
 	pub fn note(mut self, note: &str) -> Self {
 		self.notes.push(note.to_string());
 		self
 	}
 
-	pub fn applies_at(&self, time: &DateTime<Tz>) -> bool {
+	/// The labels which identify the part, such as the service or area it is
+	/// for, so that an [`Exception`](super::Exception) can target it
+	pub fn labels(&self) -> &Vec<String> {
+		&self.labels
+	}
+
+	pub fn labels_mut(&mut self) -> &mut Vec<String> {
+		&mut self.labels
+	}
+
+	pub fn label(mut self, label: &str) -> Self {
+		self.labels.push(label.to_string());
+		self
+	}
+
+	/// Find the earliest time strictly after `time` at which this part opens or
+	/// closes
+	pub fn next_transition_after(&self, time: &DateTime<Tz>) -> Option<DateTime<Tz>> {
+		self
+			.open
+			.iter()
+			.chain(self.close.iter())
+			.filter_map(|specifier| specifier.next_after(time))
+			.min()
+	}
+
+	/// Compute the windows of time between `from` and `to` during which the
+	/// part applies, each from the opening which decides it
+	///
+	/// A window ends at the next opening even if the part is still open then,
+	/// so that every time in a window has the same opening as its `since`,
+	/// just as [`Part::trace_at`] would give it.
+	pub fn windows(
+		&self,
+		from: &DateTime<Tz>,
+		to: &DateTime<Tz>,
+	) -> Result<Vec<Window<Tz>>, RuleError> {
 		match (self.open.as_ref(), self.close.as_ref()) {
-			(Some(open), Some(close)) => {
-				let open = open.instances(time).next().unwrap();
-				let close = close.instances(time).next().unwrap();
+			(Some(open), Some(close)) => Window::between(open, from, to, |opened| {
+				Self::close_after(open, close, opened).map(Some)
+			}),
+			_ => Ok(vec![Window {
+				since: None,
+				start: from.to_owned(),
+				end: to.to_owned(),
+			}]),
+		}
+	}
+
+	/// Find the close which ends the part after it opened at `opened`
+	///
+	/// This is the first close after the opening, unless the opening was moved
+	/// out of a gap when clocks sprang forward (see [`Specifier`]). Then a close
+	/// from the start of the gap onwards ends the part, and if that is at or
+	/// before the moved opening, the part doesn't open at all; so a part from
+	/// 02:00 until 03:00 is closed on the day that 02:00 jumps to 03:00.
+	///
+	/// Likewise, a close moved out of a gap which started at or before the
+	/// opening was meant for a time before it, so a part from 03:00 until 02:30
+	/// stays open through the day that 02:30 is moved to 03:30.
+	fn close_after(
+		open: &Specifier<Tz>,
+		close: &Specifier<Tz>,
+		opened: &DateTime<Tz>,
+	) -> Result<DateTime<Tz>, RuleError> {
+		let gap = Some(opened)
+			.filter(|opened| open.moved_out_of_gap(opened))
+			.and_then(gap_before);
+
+		let closes = match gap {
+			Some(gap) => close
+				.instances(&gap)
+				.find(|instance| instance >= &gap)
+				.map(|instance| {
+					if &instance < opened {
+						opened.to_owned()
+					} else {
+						instance
+					}
+				}),
+			None => close.instances(opened).find(|instance| {
+				instance > opened
+					&& !(close.moved_out_of_gap(instance)
+						&& gap_before(instance).is_some_and(|gap| &gap <= opened))
+			}),
+		};
+
+		closes.ok_or(RuleError::NoInstance("close"))
+	}
+
+	/// Determine whether the part is open at the given time
+	///
+	/// The part is open if it most recently opened at or before `time` and has
+	/// not closed since, so a part may run past midnight or the end of the week,
+	/// e.g. from Friday 22:00 until Saturday 02:00.
+	///
+	/// It is an error for the part to open without ever closing afterwards.
+	pub fn applies_at(&self, time: &DateTime<Tz>) -> Result<bool, RuleError> {
+		self.trace_at(time).map(|trace| trace.applies)
+	}
+
+	/// Determine whether the part is open at the given time, as
+	/// [`Part::applies_at`] does, along with the opening and close which decided
+	/// it
+	pub fn trace_at(&self, time: &DateTime<Tz>) -> Result<RuleTrace<Tz>, RuleError> {
+		let (open, close) = match (self.open.as_ref(), self.close.as_ref()) {
+			(Some(open), Some(close)) => (open, close),
+			_ => {
+				return Ok(RuleTrace {
+					applies: true,
+					start: None,
+					end: None,
+				})
+			}
+		};
+
+		match open.last_at_or_before(time) {
+			Some(opened) => {
+				let closes = Self::close_after(open, close, &opened)?;
 
-				(open..close).contains(time)
+				Ok(RuleTrace {
+					applies: time < &closes,
+					start: Some(opened),
+					end: Some(closes),
+				})
 			}
-			(None, Some(_)) => true,
-			(Some(_), None) => true,
-			(None, None) => true,
+			None => Ok(RuleTrace {
+				applies: false,
+				start: None,
+				end: None,
+			}),
 		}
 	}
 }
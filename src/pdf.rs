@@ -93,13 +93,13 @@ impl core::convert::TryFrom<lopdf::content::Operation> for Operation {
 			("BT", _) => Ok(Self::BeginTextObject),
 			("ET", _) => Ok(Self::EndTextObject),
 
-			("CS", opds) => match opds.get(0) {
+			("CS", opds) => match opds.first() {
 				Some(Object::Name(name)) => Ok(Self::SetColorSpaceForStrokingOperations {
 					name: name.to_vec(),
 				}),
 				_ => todo!(),
 			},
-			("cs", opds) => match opds.get(0) {
+			("cs", opds) => match opds.first() {
 				Some(Object::Name(name)) => Ok(Self::SetColorSpaceForNonstrokingOperations {
 					name: name.to_vec(),
 				}),
@@ -128,28 +128,28 @@ impl core::convert::TryFrom<lopdf::content::Operation> for Operation {
 				Ok(Self::SetColorForNonstrokingOperations)
 			}
 
-			("Tf", opds) => match (opds.get(0), opds.get(1).map(to_f64).flatten()) {
+			("Tf", opds) => match (opds.first(), opds.get(1).and_then(to_f64)) {
 				(Some(Object::Name(name)), Some(size)) => Ok(Self::SetTextFontAndSize {
 					name: name.to_vec(),
 					size,
 				}),
 				_ => todo!(),
 			},
-			("Tc", opds) => match opds.get(0).map(to_f64).flatten() {
+			("Tc", opds) => match opds.first().and_then(to_f64) {
 				Some(spacing) => Ok(Self::SetCharacterSpacing { spacing }),
 				_ => Err(error::ParseError::OperandType),
 			},
-			("Tw", opds) => match opds.get(0).map(to_f64).flatten() {
+			("Tw", opds) => match opds.first().and_then(to_f64) {
 				Some(spacing) => Ok(Self::SetWordSpacing { spacing }),
 				_ => Err(error::ParseError::OperandType),
 			},
 			("Tm", opds) => match (
-				opds.get(0).map(to_f64).flatten(),
-				opds.get(1).map(to_f64).flatten(),
-				opds.get(2).map(to_f64).flatten(),
-				opds.get(3).map(to_f64).flatten(),
-				opds.get(4).map(to_f64).flatten(),
-				opds.get(5).map(to_f64).flatten(),
+				opds.first().and_then(to_f64),
+				opds.get(1).and_then(to_f64),
+				opds.get(2).and_then(to_f64),
+				opds.get(3).and_then(to_f64),
+				opds.get(4).and_then(to_f64),
+				opds.get(5).and_then(to_f64),
 			) {
 				// N.B. In the PDF spec these use the names a, b, c, d, e, and f; these
 				// are used as generic parameters in the 3x3 transformation matrices,
@@ -159,7 +159,7 @@ impl core::convert::TryFrom<lopdf::content::Operation> for Operation {
 				}
 				_ => Err(error::ParseError::OperandType),
 			},
-			("TJ", opds) => match opds.get(0) {
+			("TJ", opds) => match opds.first() {
 				Some(Object::Array(array)) => {
 					let body: error::Result<String> = array
 						.iter()
@@ -183,7 +183,7 @@ impl core::convert::TryFrom<lopdf::content::Operation> for Operation {
 				}),
 				_ => Err(error::ParseError::OperandType),
 			},
-			("Tj", opds) => match opds.get(0) {
+			("Tj", opds) => match opds.first() {
 				Some(Object::String(bytes, _format)) => {
 					let body = String::from_utf8(bytes.to_vec()).map_err(Into::into);
 					body.map(|body: String| Self::ShowText { body })
@@ -194,27 +194,21 @@ impl core::convert::TryFrom<lopdf::content::Operation> for Operation {
 			("q", _) => Ok(Self::SaveGraphicsState),
 			("Q", _) => Ok(Self::RestoreGraphicsState),
 
-			("Td", opds) => match (
-				opds.get(0).map(to_f64).flatten(),
-				opds.get(1).map(to_f64).flatten(),
-			) {
+			("Td", opds) => match (opds.first().and_then(to_f64), opds.get(1).and_then(to_f64)) {
 				(Some(t_x), Some(t_y)) => Ok(Self::MoveTextPosition { t_x, t_y }),
 				_ => todo!(),
 			},
-			("TD", opds) => match (
-				opds.get(0).map(to_f64).flatten(),
-				opds.get(1).map(to_f64).flatten(),
-			) {
+			("TD", opds) => match (opds.first().and_then(to_f64), opds.get(1).and_then(to_f64)) {
 				(Some(t_x), Some(t_y)) => Ok(Self::MoveTextPositionAndSetLeading { t_x, t_y }),
 				_ => todo!(),
 			},
 			("T*", _) => Ok(Self::MoveToStartOfNextLine),
 
 			("re", opds) => match (
-				opds.get(0).map(to_f64).flatten(),
-				opds.get(1).map(to_f64).flatten(),
-				opds.get(2).map(to_f64).flatten(),
-				opds.get(3).map(to_f64).flatten(),
+				opds.first().and_then(to_f64),
+				opds.get(1).and_then(to_f64),
+				opds.get(2).and_then(to_f64),
+				opds.get(3).and_then(to_f64),
 			) {
 				(Some(x), Some(y), Some(width), Some(height)) => Ok(Self::AppendRectangleToPath {
 					x,
@@ -1,20 +1,23 @@
-use super::{Exception, Part};
-use chrono::{DateTime, TimeZone};
+use super::{Activity, Exception, Part};
+use chrono::{DateTime, Duration, TimeZone};
+use std::sync::Arc;
 
 #[allow(dead_code)]
 #[derive(Debug)]
-pub struct Schedule<'schedule, Tz: TimeZone> {
+pub struct Schedule<Tz: TimeZone> {
 	effective: Option<DateTime<Tz>>,
 	expires: Option<DateTime<Tz>>,
-	parts: Vec<Part<Tz>>,
-	exceptions: Vec<Exception<'schedule, Tz>>,
+	priority: i32,
+	parts: Vec<Arc<Part<Tz>>>,
+	exceptions: Vec<Exception<Tz>>,
 }
 
-impl<'schedule, Tz: TimeZone> Default for Schedule<'schedule, Tz> {
+impl<Tz: TimeZone> Default for Schedule<Tz> {
 	fn default() -> Self {
 		Self {
 			effective: None,
 			expires: None,
+			priority: 0,
 			parts: Vec::new(),
 			exceptions: Vec::new(),
 		}
@@ -22,8 +25,8 @@ impl<'schedule, Tz: TimeZone> Default for Schedule<'schedule, Tz> {
 }
 
 #[allow(dead_code)]
-impl<'schedule, Tz: TimeZone> Schedule<'schedule, Tz> {
-	pub fn new() -> Schedule<'schedule, Tz> {
+impl<Tz: TimeZone> Schedule<Tz> {
+	pub fn new() -> Schedule<Tz> {
 		Default::default()
 	}
 
@@ -43,16 +46,51 @@ impl<'schedule, Tz: TimeZone> Schedule<'schedule, Tz> {
 		&mut self.expires
 	}
 
-	pub fn parts(&self) -> &Vec<Part<Tz>> {
+	/// The priority of the schedule over others which are in effect at the same
+	/// time, which is 0 unless set; see [`Space::schedule_at`](super::Space::schedule_at)
+	pub fn priority(&self) -> i32 {
+		self.priority
+	}
+
+	pub fn priority_mut(&mut self) -> &mut i32 {
+		&mut self.priority
+	}
+
+	/// The length of time from `effective` until `expires`, or `None` if the
+	/// schedule is unbounded on either side
+	pub fn window(&self) -> Option<Duration> {
+		match (self.effective(), self.expires()) {
+			(Some(start), Some(end)) => Some(end.clone() - start.clone()),
+			_ => None,
+		}
+	}
+
+	/// Determine whether the schedule is in effect at the given time, from
+	/// `effective` (inclusive) until `expires` (exclusive)
+	pub fn is_active_at(&self, time: &DateTime<Tz>) -> bool {
+		self.activity_at(time) == Activity::Active
+	}
+
+	/// Determine whether the schedule is in effect at the given time, and if
+	/// not, which of its bounds excludes it
+	pub fn activity_at(&self, time: &DateTime<Tz>) -> Activity {
+		match (self.effective(), self.expires()) {
+			(Some(start), _) if time < start => Activity::NotYetEffective,
+			(_, Some(end)) if time >= end => Activity::Expired,
+			_ => Activity::Active,
+		}
+	}
+
+	pub fn parts(&self) -> &Vec<Arc<Part<Tz>>> {
 		&self.parts
 	}
 
-	pub fn parts_mut(&mut self) -> &mut Vec<Part<Tz>> {
+	pub fn parts_mut(&mut self) -> &mut Vec<Arc<Part<Tz>>> {
 		&mut self.parts
 	}
 
 	pub fn part(mut self, part: Part<Tz>) -> Self {
-		self.parts.push(part);
+		self.parts.push(Arc::new(part));
 		self
 	}
 
@@ -60,11 +98,11 @@ impl<'schedule, Tz: TimeZone> Schedule<'schedule, Tz> {
 		&self.exceptions
 	}
 
-	pub fn exceptions_mut(&mut self) -> &mut Vec<Exception<'schedule, Tz>> {
+	pub fn exceptions_mut(&mut self) -> &mut Vec<Exception<Tz>> {
 		&mut self.exceptions
 	}
 
-	pub fn exception(mut self, exception: Exception<'schedule, Tz>) -> Self {
+	pub fn exception(mut self, exception: Exception<Tz>) -> Self {
 		self.exceptions.push(exception);
 		self
 	}
@@ -1,33 +1,341 @@
-use super::{Exception, Part, Reason, Schedule, Status, StatusChange};
-use chrono::{DateTime, TimeZone};
+use super::{
+	Activity, Clock, CompiledSpace, Conflict, ExceptionPrecedence, Explanation, Part, Reason, Rule,
+	RuleError, Schedule, ScheduleTrace, Status, StatusChange, StatusError, StatusIndex, Timeline,
+};
+use chrono::{DateTime, Duration, TimeZone};
+use log::warn;
+use std::cmp::{Ordering, Reverse};
+use std::sync::Arc;
+
+/// How far past the basis time [`Space::next_status_change_at`] will look for
+/// a change before giving up.
+const STATUS_CHANGE_LOOKAHEAD_DAYS: i64 = 366;
+
+/// An exception which applies, along with the time at which it took effect
+/// and the status it gives the space
+struct Candidate<Tz: TimeZone> {
+	position: usize,
+	since: Option<DateTime<Tz>>,
+	effect: Status<Tz>,
+}
+
+/// The outcome of [`Space::resolve_exceptions`]
+pub(crate) struct Resolution<Tz: TimeZone> {
+	/// The position of the exception which decides the status, and the status
+	pub winner: Option<(usize, Status<Tz>)>,
+	/// The positions of the exceptions which conflict with the winner
+	pub conflicts: Vec<usize>,
+}
+
+/// The outcome of [`Space::part_status`]
+pub(crate) struct PartStatus<Tz: TimeZone> {
+	/// The part or exception which decided the status, if any
+	pub decided_by: Option<Rule>,
+	pub status: Status<Tz>,
+	/// The positions of exceptions targeting a part which conflict with the one
+	/// which decided whether it is open
+	pub conflicts: Vec<usize>,
+}
 
 #[allow(dead_code)]
 #[derive(Debug)]
-pub struct Space<'schedule, Tz: TimeZone> {
+pub struct Space<Tz: TimeZone> {
 	name: String,
-	schedules: Vec<Schedule<'schedule, Tz>>,
+	schedules: Vec<Schedule<Tz>>,
+	exception_precedence: ExceptionPrecedence,
 }
 
-impl<'schedule, Tz: TimeZone> Default for Space<'schedule, Tz> {
+impl<Tz: TimeZone> Default for Space<Tz> {
 	fn default() -> Self {
 		Self {
 			name: String::new(),
 			schedules: Vec::new(),
+			exception_precedence: ExceptionPrecedence::default(),
 		}
 	}
 }
 
-impl<'schedule, Tz: TimeZone> Space<'schedule, Tz> {
-	pub fn schedule(mut self, schedule: Schedule<'schedule, Tz>) -> Self {
+impl<Tz: TimeZone> Space<Tz> {
+	pub fn schedule(mut self, schedule: Schedule<Tz>) -> Self {
 		self.schedules.push(schedule);
 		self
 	}
+
+	/// Set how to choose between exceptions of the same priority which apply at
+	/// the same time
+	pub fn exception_precedence(mut self, precedence: ExceptionPrecedence) -> Self {
+		self.exception_precedence = precedence;
+		self
+	}
+
+	pub(crate) fn schedules(&self) -> &Vec<Schedule<Tz>> {
+		&self.schedules
+	}
+
+	/// Choose the exception which decides the status from those of the given
+	/// schedule which apply, each given by its position in the schedule and the
+	/// time at which it took effect, if known
+	///
+	/// `effect_of` gives the status which each exception gives the space, as
+	/// [`Exception::effect_at`](super::Exception::effect_at) does.
+	///
+	/// Exceptions which have no say in the status, including those which target
+	/// specific parts, are ignored. The winner and conflicts among the rest are
+	/// found as described for [`Space::choose`].
+	///
+	/// This runs for every status query, so conflicts aren't logged here; they
+	/// are reported by [`Space::explain_at`] in [`Explanation::conflicts`], and
+	/// over a range of time by [`Space::conflicts`].
+	pub(crate) fn resolve_exceptions(
+		&self,
+		schedule: usize,
+		applicable: &[(usize, Option<&DateTime<Tz>>)],
+		effect_of: impl Fn(usize) -> Result<Option<Status<Tz>>, RuleError>,
+	) -> Result<Resolution<Tz>, StatusError> {
+		let exceptions = self.schedules[schedule].exceptions();
+
+		let mut candidates: Vec<Candidate<Tz>> = Vec::new();
+		for (position, since) in applicable.iter() {
+			if !exceptions[*position].targets().is_empty() {
+				continue;
+			}
+
+			let effect = effect_of(*position)
+				.map_err(|error| self.error(schedule, Rule::Exception(*position), error))?;
+
+			if let Some(effect) = effect {
+				candidates.push(Candidate {
+					position: *position,
+					since: since.cloned(),
+					effect,
+				});
+			}
+		}
+
+		Ok(self.choose(schedule, candidates))
+	}
+
+	/// Choose the winner among exceptions of the given schedule which would
+	/// each decide the same status, and find those which conflict with it
+	///
+	/// The winner is the one with the highest priority, with ties broken by the
+	/// space's [`ExceptionPrecedence`], then by position. Any others which
+	/// disagree with the winner, opening where it closes or vice versa, are
+	/// conflicts.
+	fn choose(&self, schedule: usize, candidates: Vec<Candidate<Tz>>) -> Resolution<Tz> {
+		let exceptions = self.schedules[schedule].exceptions();
+
+		let outranks = |a: &Candidate<Tz>, b: &Candidate<Tz>| {
+			let tie_break = match self.exception_precedence {
+				ExceptionPrecedence::FirstAdded => Ordering::Equal,
+				ExceptionPrecedence::MostRecent => a.since.cmp(&b.since),
+				ExceptionPrecedence::ClosuresFirst => b.effect.is_open().cmp(&a.effect.is_open()),
+			};
+
+			exceptions[a.position]
+				.priority()
+				.cmp(&exceptions[b.position].priority())
+				.then(tie_break)
+				== Ordering::Greater
+		};
+
+		let mut winner = match candidates.first() {
+			Some(first) => first,
+			None => {
+				return Resolution {
+					winner: None,
+					conflicts: Vec::new(),
+				}
+			}
+		};
+		for candidate in candidates.iter().skip(1) {
+			if outranks(candidate, winner) {
+				winner = candidate;
+			}
+		}
+
+		let conflicts: Vec<usize> = candidates
+			.iter()
+			.filter(|candidate| candidate.effect.is_open() != winner.effect.is_open())
+			.map(|candidate| candidate.position)
+			.collect();
+
+		Resolution {
+			winner: Some((winner.position, winner.effect.clone())),
+			conflicts,
+		}
+	}
+
+	/// Compute the status given by the parts of a schedule when no exception
+	/// decides it, along with the part or exception which decided it
+	///
+	/// `replacement` is the first open part of an exception which replaces the
+	/// schedule's parts, with the exception's position, as found by
+	/// [`Exception::replacement_at`](super::Exception::replacement_at).
+	/// `parts` are the positions of the schedule's own parts which apply at the
+	/// time and aren't replaced, as found by [`Space::is_replaced`], in order.
+	/// `applicable` are the exceptions which apply, as for
+	/// [`Space::resolve_exceptions`].
+	///
+	/// Whether each part is open is decided by the exceptions among those which
+	/// target it, chosen as described for [`Space::choose`], and otherwise by
+	/// whether it applies. If a part is open, the space is open for the first
+	/// such part, with [`Reason::Reduced`] if any which apply were closed;
+	/// otherwise it is closed.
+	pub(crate) fn part_status<'a>(
+		&'a self,
+		schedule: usize,
+		replacement: Option<(usize, &'a Arc<Part<Tz>>)>,
+		parts: impl IntoIterator<Item = usize>,
+		applicable: &[(usize, Option<&DateTime<Tz>>)],
+	) -> PartStatus<Tz> {
+		let index = schedule;
+		let schedule = &self.schedules[index];
+		let exceptions = schedule.exceptions();
+
+		let mut applying = parts.into_iter().peekable();
+		let candidates = (replacement.into_iter())
+			.map(|(exception, part)| (Rule::Exception(exception), part, true))
+			.chain(schedule.parts().iter().enumerate().map(|(position, part)| {
+				let applies = applying.next_if_eq(&position).is_some();
+				(Rule::Part(position), part, applies)
+			}));
+
+		let mut open: Option<(Rule, &Arc<Part<Tz>>)> = None;
+		let mut closed: Vec<Arc<Part<Tz>>> = Vec::new();
+		let mut closed_by: Option<(usize, Status<Tz>)> = None;
+		let mut conflicts: Vec<usize> = Vec::new();
+
+		for (rule, part, applies) in candidates {
+			// An exception which targets a part closes it unless its effect opens it.
+			let targeting: Vec<Candidate<Tz>> = (applicable.iter())
+				.filter(|(exception, _)| exceptions[*exception].targets_part(part))
+				.map(|(exception, since)| Candidate {
+					position: *exception,
+					since: since.cloned(),
+					effect: (exceptions[*exception].effect().clone())
+						.unwrap_or(Status::Closed(Reason::Exception(None))),
+				})
+				.collect();
+			let resolution = self.choose(index, targeting);
+
+			for conflict in resolution.conflicts {
+				if !conflicts.contains(&conflict) {
+					conflicts.push(conflict);
+				}
+			}
+
+			match resolution.winner {
+				Some((exception, effect)) if effect.is_open() => {
+					open.get_or_insert((Rule::Exception(exception), part));
+				}
+				Some((exception, effect)) => {
+					if applies {
+						closed.push(Arc::clone(part));
+						closed_by.get_or_insert((exception, effect));
+					}
+				}
+				None => {
+					if applies {
+						open.get_or_insert((rule, part));
+					}
+				}
+			}
+		}
+
+		let (decided_by, status) = match (open, closed_by) {
+			(Some((rule, part)), _) => {
+				let part = Arc::clone(part);
+				let reason = if closed.is_empty() {
+					Reason::Part(Some(part))
+				} else {
+					Reason::Reduced { part, closed }
+				};
+
+				(Some(rule), Status::Open(reason))
+			}
+			(None, Some((exception, effect))) => (Some(Rule::Exception(exception)), effect),
+			(None, None) => {
+				// An exception which replaces the schedule's parts decides that the
+				// space is closed while none of its own parts are open.
+				let replacing = applicable
+					.iter()
+					.map(|(exception, _)| *exception)
+					.find(|exception| exceptions[*exception].is_replacement());
+
+				(
+					replacing.map(Rule::Exception),
+					Status::Closed(Reason::Part(None)),
+				)
+			}
+		};
+
+		PartStatus {
+			decided_by,
+			status,
+			conflicts,
+		}
+	}
+
+	/// Determine whether a part of the given schedule which opened at `opened`
+	/// is replaced, because an exception which replaces the schedule's parts
+	/// applied then
+	pub(crate) fn is_replaced(
+		&self,
+		schedule: usize,
+		opened: &DateTime<Tz>,
+	) -> Result<bool, StatusError> {
+		for (position, exception) in self.schedules[schedule].exceptions().iter().enumerate() {
+			if !exception.is_replacement() {
+				continue;
+			}
+
+			let applies = exception
+				.applies_at(opened)
+				.map_err(|error| self.error(schedule, Rule::Exception(position), error))?;
+
+			if applies {
+				return Ok(true);
+			}
+		}
+
+		Ok(false)
+	}
+
+	/// Attribute an error in a part or exception to the given schedule of this
+	/// space
+	pub(crate) fn error(&self, schedule: usize, rule: Rule, error: RuleError) -> StatusError {
+		StatusError {
+			space: self.name.to_owned(),
+			schedule,
+			rule,
+			error,
+		}
+	}
+
+	/// Compute the earliest instant strictly after `time` at which any
+	/// schedule, part or exception of this space begins or ends
+	pub(crate) fn next_transition_after(&self, time: &DateTime<Tz>) -> Option<DateTime<Tz>> {
+		let mut candidates: Vec<DateTime<Tz>> = Vec::new();
+
+		for schedule in self.schedules.iter() {
+			candidates.extend(schedule.effective().iter().cloned());
+			candidates.extend(schedule.expires().iter().cloned());
+
+			for part in schedule.parts() {
+				candidates.extend(part.next_transition_after(time));
+			}
+
+			for exception in schedule.exceptions() {
+				candidates.extend(exception.next_transition_after(time));
+			}
+		}
+
+		candidates.into_iter().filter(|c| c > time).min()
+	}
 }
 
-impl<'schedule, Tz: TimeZone> Space<'schedule, Tz>
-where
-	DateTime<Tz>: core::convert::From<DateTime<chrono::offset::Local>>,
-{
+impl<Tz: TimeZone> Space<Tz> {
 	pub fn new(name: &str) -> Space<Tz> {
 		Space {
 			name: name.to_string(),
@@ -35,83 +343,283 @@ where
 		}
 	}
 
-	/// Compute the status of the space at the current time
-	pub fn status(&'schedule self) -> Status<'schedule, Tz> {
-		use chrono::offset::Local;
-		let now: DateTime<Local> = Local::now();
-		self.status_at(&DateTime::from(now))
+	/// Find the schedule which is in effect at the given time, along with its
+	/// position in the space
+	///
+	/// When several schedules are in effect at once, only one of them is used:
+	/// the one with the highest priority, then the one with the narrowest window
+	/// from `effective` to `expires`, then the one added to the space first. So
+	/// a schedule for a holiday break takes precedence over the regular one for
+	/// the year around it.
+	///
+	/// The exceptions of the other schedules are ignored along with their parts,
+	/// so an exception which should still apply during the break, such as a
+	/// campus-wide closure, must be added to the break's schedule as well.
+	pub fn schedule_at(&self, time: &DateTime<Tz>) -> Option<(usize, &Schedule<Tz>)> {
+		self
+			.schedules
+			.iter()
+			.enumerate()
+			.filter(|(_, schedule)| schedule.is_active_at(time))
+			.min_by_key(|(_, schedule)| {
+				let window = schedule.window();
+				(Reverse(schedule.priority()), window.is_none(), window)
+			})
 	}
 
 	/// Compute the status of the space at the given time
-	// TODO Make actually functional
-	pub fn status_at(&'schedule self, time: &DateTime<Tz>) -> Status<'schedule, Tz> {
-		let active_schedules: Vec<&Schedule<'schedule, Tz>> = self
-			.schedules
-			.iter()
-			.filter(
-				|schedule| match (schedule.effective(), schedule.expires()) {
-					(Some(start), None) => start < time,
-					(Some(start), Some(end)) => start < time && time < end,
-					(None, Some(end)) => time < end,
-					(None, None) => true,
-				},
-			)
-			.collect();
+	///
+	/// An error is returned if a part or exception of an active schedule can't
+	/// be evaluated at that time.
+	pub fn status_at(&self, time: &DateTime<Tz>) -> Result<Status<Tz>, StatusError> {
+		self.explain_at(time).map(|explanation| explanation.status)
+	}
 
-		// TODO consider selecting the "most specific" schedule?
+	/// Compute the status of the space at the given time, along with a trace of
+	/// how each schedule, part and exception was evaluated and which of them
+	/// decided the status
+	///
+	/// See [`Explanation`].
+	pub fn explain_at(&self, time: &DateTime<Tz>) -> Result<Explanation<Tz>, StatusError> {
+		let chosen = self.schedule_at(time).map(|(index, _)| index);
 
-		let parts: Vec<&Part<Tz>> = active_schedules
-			.iter()
-			.map(|schedule| schedule.parts())
-			.flatten()
-			.collect();
+		let mut schedules: Vec<ScheduleTrace<Tz>> = Vec::new();
+		let mut decided_by: Option<(usize, Rule)> = None;
+		let mut status = Status::Closed(Reason::Part(None));
+		let mut conflicts: Vec<(usize, Rule)> = Vec::new();
 
-		let exceptions: Vec<&Exception<Tz>> = active_schedules
-			.iter()
-			.map(|schedule| schedule.exceptions())
-			.flatten()
-			.collect();
+		for (index, schedule) in self.schedules.iter().enumerate() {
+			let mut trace = ScheduleTrace {
+				activity: schedule.activity_at(time),
+				parts: Vec::new(),
+				exceptions: Vec::new(),
+			};
 
-		let current_parts: Vec<&Part<Tz>> = parts
-			.iter()
-			.cloned()
-			.filter(|p| p.applies_at(time))
-			.collect();
+			if chosen != Some(index) {
+				if trace.activity == Activity::Active {
+					trace.activity = Activity::Superseded;
+				}
+				schedules.push(trace);
+				continue;
+			}
 
-		let current_exceptions: Vec<&Exception<Tz>> = exceptions
-			.iter()
-			.cloned()
-			.filter(|e| e.applies_at(time))
-			.collect();
+			for (position, part) in schedule.parts().iter().enumerate() {
+				let part_trace = part
+					.trace_at(time)
+					.map_err(|error| self.error(index, Rule::Part(position), error))?;
 
-		eprintln!("{}, x{}", current_parts.len(), current_exceptions.len());
+				trace.parts.push(part_trace);
+			}
 
-		if !current_exceptions.is_empty() {
-			let exception = current_exceptions[0];
+			for (position, exception) in schedule.exceptions().iter().enumerate() {
+				let exception_trace = exception
+					.trace_at(time)
+					.map_err(|error| self.error(index, Rule::Exception(position), error))?;
 
-			let effect = exception.effect();
+				trace.exceptions.push(exception_trace);
+			}
 
-			if let Some(effect) = effect {
-				return (*effect).clone();
+			let applicable: Vec<(usize, Option<&DateTime<Tz>>)> = (trace.exceptions.iter().enumerate())
+				.filter(|(_, exception_trace)| exception_trace.applies)
+				.map(|(position, exception_trace)| (position, exception_trace.start.as_ref()))
+				.collect();
+			let resolution = self.resolve_exceptions(index, &applicable, |position| {
+				schedule.exceptions()[position].effect_at(time)
+			})?;
+
+			match resolution.winner {
+				Some((position, effect)) => {
+					decided_by = Some((index, Rule::Exception(position)));
+					status = effect;
+				}
+				None => {
+					let mut replacement = None;
+					for (position, exception) in schedule.exceptions().iter().enumerate() {
+						if !exception.is_replacement() {
+							continue;
+						}
+
+						replacement = exception
+							.replacement_at(time)
+							.map_err(|error| self.error(index, Rule::Exception(position), error))?
+							.map(|part| (position, part));
+						if replacement.is_some() {
+							break;
+						}
+					}
+
+					let mut parts: Vec<usize> = Vec::new();
+					for (position, part_trace) in trace.parts.iter().enumerate() {
+						let opened = part_trace.start.as_ref().unwrap_or(time);
+
+						if part_trace.applies && !self.is_replaced(index, opened)? {
+							parts.push(position);
+						}
+					}
+
+					let part_status = self.part_status(index, replacement, parts, &applicable);
+
+					decided_by = part_status.decided_by.map(|rule| (index, rule));
+					status = part_status.status;
+					conflicts.extend(
+						(part_status.conflicts.into_iter()).map(|position| (index, Rule::Exception(position))),
+					);
+				}
+			}
+			conflicts.extend(
+				resolution
+					.conflicts
+					.into_iter()
+					.map(|position| (index, Rule::Exception(position))),
+			);
+
+			schedules.push(trace);
+		}
+
+		Ok(Explanation {
+			space: self.name.to_owned(),
+			time: time.to_owned(),
+			schedules,
+			schedule: chosen,
+			decided_by,
+			conflicts,
+			status,
+		})
+	}
+
+	/// Find the stretches of time between `from` and `to` during which
+	/// exceptions conflict, as [`Space::explain_at`] reports at each time in
+	/// [`Explanation::conflicts`], and log each one as a warning
+	///
+	/// This checks the space once rather than at every status query, such as
+	/// when it is loaded.
+	pub fn conflicts(
+		&self,
+		from: &DateTime<Tz>,
+		to: &DateTime<Tz>,
+	) -> Result<Vec<Conflict<Tz>>, StatusError> {
+		let mut conflicts: Vec<Conflict<Tz>> = Vec::new();
+		let mut start = from.to_owned();
+
+		// Which exceptions apply only changes at a transition.
+		while &start < to {
+			let end = match self.next_transition_after(&start) {
+				Some(end) if &end < to => end,
+				_ => to.to_owned(),
+			};
+			let explanation = self.explain_at(&start)?;
+
+			if !explanation.conflicts.is_empty() {
+				match conflicts.last_mut() {
+					Some(last)
+						if last.decided_by == explanation.decided_by
+							&& last.overridden == explanation.conflicts
+							&& last.end == start =>
+					{
+						last.end = end.to_owned()
+					}
+					_ => conflicts.push(Conflict {
+						start: start.to_owned(),
+						end: end.to_owned(),
+						decided_by: explanation.decided_by,
+						overridden: explanation.conflicts,
+					}),
+				}
+			}
+
+			start = end;
+		}
+
+		for conflict in conflicts.iter() {
+			for (schedule, rule) in conflict.overridden.iter() {
+				warn!(
+					"space {:?}, from {:?} until {:?}: schedule {}, {} is overridden",
+					self.name, conflict.start, conflict.end, schedule, rule
+				);
 			}
 		}
 
-		if !current_parts.is_empty() {
-			let part = current_parts[0];
-			Status::Open(Reason::Part(Some(part)))
-		} else {
-			Status::Closed(Reason::Part(None))
+		Ok(conflicts)
+	}
+
+	/// Precompute the parts of every schedule over the window from `from` to
+	/// `to`, for answering many status queries quickly
+	///
+	/// See [`CompiledSpace`].
+	pub fn compile(
+		&self,
+		from: &DateTime<Tz>,
+		to: &DateTime<Tz>,
+	) -> Result<CompiledSpace<'_, Tz>, StatusError> {
+		CompiledSpace::new(self, from, to)
+	}
+
+	/// Precompute every change in the status of the space over the window from
+	/// `from` to `to`, so that status queries become binary searches
+	///
+	/// See [`StatusIndex`].
+	pub fn index(
+		&self,
+		from: &DateTime<Tz>,
+		to: &DateTime<Tz>,
+	) -> Result<StatusIndex<'_, Tz>, StatusError>
+	where
+		Status<Tz>: PartialEq,
+	{
+		StatusIndex::new(self, from, to)
+	}
+
+	/// Iterate over the stretches of time between `from` and `to` during which
+	/// the status of the space stays the same
+	pub fn timeline(&self, from: &DateTime<Tz>, to: &DateTime<Tz>) -> Timeline<'_, Tz> {
+		Timeline::new(self, from, to)
+	}
+
+	/// Compute the next time after the given time at which the space opens or
+	/// closes, along with the reason for the new status
+	///
+	/// Only changes within a year of `time` are found; if the space stays open
+	/// or closed for longer than that, `None` is returned.
+	pub fn next_status_change_at(
+		&self,
+		time: &DateTime<Tz>,
+	) -> Result<Option<StatusChange<Tz>>, StatusError> {
+		let horizon = time.clone() + Duration::days(STATUS_CHANGE_LOOKAHEAD_DAYS);
+		let currently_open = self.status_at(time)?.is_open();
+
+		let mut basis = time.clone();
+
+		while let Some(candidate) = self.next_transition_after(&basis) {
+			if candidate > horizon {
+				break;
+			}
+
+			match self.status_at(&candidate)? {
+				Status::Open(reason) if !currently_open => {
+					return Ok(Some(StatusChange::Opening(candidate, reason)))
+				}
+				Status::Closed(reason) if currently_open => {
+					return Ok(Some(StatusChange::Closing(candidate, reason)))
+				}
+				_ => basis = candidate,
+			}
 		}
+
+		Ok(None)
 	}
 
-	pub fn next_status_change(&self) -> Option<StatusChange<Tz>> {
-		use chrono::offset::Local;
-		let now: DateTime<Local> = Local::now();
-		self.next_status_change_at(&DateTime::from(now))
+	/// Compute the status of the space at the current time, according to the
+	/// given clock
+	pub fn status(&self, clock: &impl Clock<Tz>) -> Result<Status<Tz>, StatusError> {
+		self.status_at(&clock.now())
 	}
 
-	// TODO Make actually functional
-	pub fn next_status_change_at(&self, _time: &DateTime<Tz>) -> Option<StatusChange<Tz>> {
-		None
+	/// Compute the next time at which the space opens or closes, starting from
+	/// the current time according to the given clock
+	pub fn next_status_change(
+		&self,
+		clock: &impl Clock<Tz>,
+	) -> Result<Option<StatusChange<Tz>>, StatusError> {
+		self.next_status_change_at(&clock.now())
 	}
 }
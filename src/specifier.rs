@@ -1,24 +1,213 @@
-use chrono::{prelude::*, DateTime, TimeZone};
+use chrono::{offset::LocalResult, prelude::*, DateTime, Duration, TimeZone};
+
+mod error;
+mod rrule;
+mod solar;
+mod weekday_set;
+
+pub use error::SpecifierError;
+pub use rrule::{Frequency, RRule};
+pub use solar::SolarEvent;
+pub use weekday_set::WeekdaySet;
 
 /// A specifier for when something happens.
+///
+/// Patterns are computed in the local time of the time zone they are evaluated
+/// in, so "07:00" stays at 07:00 on either side of a daylight saving time
+/// transition. Local times skipped by a transition are moved later by the length
+/// of the gap, and local times repeated by one resolve to their first
+/// occurrence.
 #[allow(dead_code)]
 #[derive(Clone, Debug, PartialEq)]
 pub enum Specifier<Tz: TimeZone> {
 	/// A pattern of days and times which must be computed against to give a
 	/// definitive answer.
-	Weekly { day: String, time: String },
+	Weekly { day: Weekday, time: NaiveTime },
 
 	/// A pattern of times
-	Daily { time: String },
+	Daily { time: NaiveTime },
+
+	/// A time on each of a set of days of the week, such as Monday through
+	/// Friday
+	Weekdays { days: WeekdaySet, time: NaiveTime },
+
+	/// Every given number of days, counted from an anchor date which sets which
+	/// days match, such as "every other Tuesday" or "every third day"
+	Interval {
+		anchor: NaiveDate,
+		days: u32,
+		time: NaiveTime,
+	},
+
+	/// A day of the month, such as "the 15th of each month"; months which are
+	/// too short to have that day are skipped.
+	Monthly { day: u32, time: NaiveTime },
+
+	/// The nth (starting from 1) given weekday of the month, such as "the first
+	/// Monday of each month"; months without an nth such weekday are skipped.
+	MonthlyNthWeekday {
+		nth: u32,
+		day: Weekday,
+		time: NaiveTime,
+	},
+
+	/// The last given weekday of the month, such as "the last Friday of each
+	/// month"
+	MonthlyLastWeekday { day: Weekday, time: NaiveTime },
+
+	/// A fixed date every year, such as "December 25th". February 29th only
+	/// happens in leap years; other years are skipped.
+	Yearly {
+		month: u32,
+		day: u32,
+		time: NaiveTime,
+	},
+
+	/// A daily event in the path of the sun, such as sunrise, computed for a
+	/// place given in degrees north and east. Days on which the event doesn't
+	/// happen, such as during the midnight sun, are skipped.
+	Solar {
+		event: SolarEvent,
+		latitude: f64,
+		longitude: f64,
+	},
+
+	/// An RFC 5545 recurrence rule, usually created with
+	/// [`Specifier::from_rrule`]
+	Recurrence(RRule),
+
+	/// The instances of another specifier, shifted by a (possibly negative)
+	/// offset, such as "30 minutes before close"
+	Offset {
+		specifier: Box<Specifier<Tz>>,
+		offset: Duration,
+	},
+
+	/// The instances of another specifier which fall between optional start
+	/// and end bounds (both inclusive), such as "every Tuesday from January 14th
+	/// until May 5th"
+	Bounded {
+		specifier: Box<Specifier<Tz>>,
+		from: Option<DateTime<Tz>>,
+		until: Option<DateTime<Tz>>,
+	},
 
 	/// An exact time
 	Exact(DateTime<Tz>),
 }
 
+/// How many months to look through for a monthly instance before deciding
+/// that the specifier can never match (e.g. the 32nd of the month)
+const MONTHLY_SEARCH_LIMIT: u32 = 12;
+
+/// How many days to look through for a solar event before deciding that it
+/// never happens at that place
+const SOLAR_SEARCH_LIMIT: i64 = 366;
+
+/// How many years to look through for a yearly instance before deciding that
+/// the specifier can never match; leap days can be up to eight years apart.
+const YEARLY_SEARCH_LIMIT: i32 = 8;
+
+/// How far back to look for an earlier instance before deciding that there are
+/// none; this covers the longest gaps between instances of any pattern.
+const BACKWARD_SEARCH_LIMIT_DAYS: i64 = 9 * 366;
+
+/// The longest stretch of time to search in one go when looking backwards
+const BACKWARD_WINDOW_LIMIT_DAYS: i64 = 366;
+
+fn parse_time(time: &str) -> error::Result<NaiveTime> {
+	NaiveTime::parse_from_str(time, "%H:%M")
+		.or_else(|_| NaiveTime::parse_from_str(time, "%H:%M:%S"))
+		.map_err(|_| SpecifierError::InvalidTime(time.to_string()))
+}
+
+/// Compute the first day of the month following the one `date` is in
+fn first_of_next_month(date: NaiveDate) -> NaiveDate {
+	match date.month() {
+		12 => NaiveDate::from_ymd(date.year() + 1, 1, 1),
+		month => NaiveDate::from_ymd(date.year(), month + 1, 1),
+	}
+}
+
+/// Resolve a local date and time to an instant in the given time zone
+///
+/// Local times which don't exist, because they fall in a gap when clocks spring
+/// forward, are moved later by the length of the gap: 02:30 on a day where
+/// 02:00 jumps to 03:00 becomes 03:30. Local times which happen twice, because
+/// they fall in an overlap when clocks fall back, resolve to the earlier of the
+/// two instants.
+fn localize<Tz: TimeZone>(tz: &Tz, local: NaiveDateTime) -> DateTime<Tz> {
+	match tz.from_local_datetime(&local) {
+		LocalResult::Single(instant) => instant,
+		LocalResult::Ambiguous(earliest, _) => earliest,
+		LocalResult::None => {
+			// Interpret the time using the offset in effect just before the gap.
+			let offset = (1..=48)
+				.map(|minutes| local - Duration::minutes(30 * minutes))
+				.find_map(|before| tz.offset_from_local_datetime(&before).earliest())
+				.expect("no valid local time in the 24 hours before a gap")
+				.fix();
+
+			tz.from_utc_datetime(&(local - Duration::seconds(offset.local_minus_utc().into())))
+		}
+	}
+}
+
+/// Find when clocks last sprang forward before `instant`, if `instant` is within
+/// the length of that gap after it
+///
+/// [`localize`] moves local times in the gap to just such instants, so an
+/// instance at `instant` may have been meant for a local time before it; see
+/// [`Specifier::moved_out_of_gap`].
+pub(crate) fn gap_before<Tz: TimeZone>(instant: &DateTime<Tz>) -> Option<DateTime<Tz>> {
+	let tz = instant.timezone();
+	let offset_at = |timestamp: i64| {
+		tz.offset_from_utc_datetime(&NaiveDateTime::from_timestamp(timestamp, 0))
+			.fix()
+			.local_minus_utc()
+	};
+
+	// As in localize, gaps are assumed to last no longer than a day.
+	let timestamp = instant.timestamp();
+	let after = offset_at(timestamp);
+	let gap = i64::from(after - offset_at(timestamp - 86_400));
+	if gap <= 0 {
+		return None;
+	}
+
+	// Find the first second with the later offset.
+	let (mut low, mut high) = (timestamp - 86_400, timestamp);
+	while high - low > 1 {
+		let middle = low + (high - low) / 2;
+		if offset_at(middle) == after {
+			high = middle;
+		} else {
+			low = middle;
+		}
+	}
+
+	Some(tz.timestamp(high, 0)).filter(|_| timestamp - high < gap)
+}
+
 #[derive(Debug)]
 pub struct Instances<'iteration, Tz: TimeZone> {
 	specifier: &'iteration Specifier<Tz>,
 	basis: DateTime<Tz>,
+	started: bool,
+	inner: Option<Box<Instances<'iteration, Tz>>>,
+}
+
+impl<'iteration, Tz: TimeZone> Instances<'iteration, Tz> {
+	/// Produce the instance at `time` on the given local date, moving the basis
+	/// to the start of the following day
+	fn advance_to(&mut self, date: NaiveDate, time: NaiveTime) -> DateTime<Tz> {
+		let tz = self.basis.timezone();
+		let instance = localize(&tz, date.and_time(time));
+
+		self.basis = localize(&tz, date.succ().and_hms(0, 0, 0));
+
+		instance
+	}
 }
 
 impl<'iteration, Tz: TimeZone> Iterator for Instances<'iteration, Tz> {
@@ -26,81 +215,442 @@ impl<'iteration, Tz: TimeZone> Iterator for Instances<'iteration, Tz> {
 
 	fn next(&mut self) -> Option<Self::Item> {
 		match self.specifier {
-			Specifier::Exact(dt) if dt != &self.basis => {
-				let next = dt.to_owned();
+			// An exact time happens exactly once, even if it is the basis itself
+			Specifier::Exact(_) if self.started => None,
+			Specifier::Exact(dt) => {
+				self.started = true;
 				self.basis = dt.to_owned();
-				Some(next)
+				Some(dt.to_owned())
 			}
-			Specifier::Exact(dt) if dt == &self.basis => None,
-			Specifier::Exact(_) => panic!(),
 			Specifier::Weekly { day, time } => {
-				let specifier_day: chrono::Weekday = day.parse().expect("invalid day specifier");
-				let specifier_time: chrono::NaiveTime = NaiveTime::parse_from_str(time, "%H:%M")
-					.or_else(|_| NaiveTime::parse_from_str(time, "%H:%M:%S"))
-					.expect("invalid time specifier");
+				let basis_date = self.basis.naive_local().date();
 
-				// If the basis weekday is the same as the specifier, then return today's instance
-				if self.basis.weekday() == specifier_day {
-					let instance = self.basis.date().and_time(specifier_time).unwrap();
+				// Count the days forward to the next matching weekday, which is today if
+				// the basis falls on the right day.
+				let difference =
+					(7 + day.num_days_from_monday() - basis_date.weekday().num_days_from_monday()) % 7;
 
-					self.basis = self.basis.clone() + chrono::Duration::weeks(1);
+				Some(self.advance_to(basis_date + Duration::days(difference.into()), *time))
+			}
+			Specifier::Daily { time } => {
+				let basis_date = self.basis.naive_local().date();
 
-					Some(instance)
-				} else {
-					let basis_weekday: i64 = self.basis.date().weekday().num_days_from_monday().into();
-					let next_instance_weekday: i64 = specifier_day.num_days_from_monday().into();
-					let mut difference = next_instance_weekday - basis_weekday;
+				Some(self.advance_to(basis_date, *time))
+			}
+			Specifier::Weekdays { days, time } => {
+				let basis_date = self.basis.naive_local().date();
 
-					// We must add days to get to the next updates.
-					if difference < 0 {
-						difference += 7;
-					}
+				let date = (0..7)
+					.map(|offset| basis_date + Duration::days(offset))
+					.find(|date| days.contains(date.weekday()))?;
+
+				Some(self.advance_to(date, *time))
+			}
+			Specifier::Interval { days: 0, .. } => None,
+			Specifier::Interval { anchor, days, time } => {
+				let basis_date = self.basis.naive_local().date();
 
-					let offset = chrono::Duration::days(difference);
+				// Count the days forward to the next date in step with the anchor,
+				// which may be before or after the basis.
+				let days = i64::from(*days);
+				let since_anchor = (basis_date - *anchor).num_days().rem_euclid(days);
+				let difference = (days - since_anchor) % days;
 
-					let instance = (self.basis.date() + offset)
-						.and_time(specifier_time)
-						.unwrap();
+				Some(self.advance_to(basis_date + Duration::days(difference), *time))
+			}
+			Specifier::Monthly { time, .. }
+			| Specifier::MonthlyNthWeekday { time, .. }
+			| Specifier::MonthlyLastWeekday { time, .. } => {
+				let specifier_time: chrono::NaiveTime = *time;
 
-					self.basis = self.basis.to_owned() + chrono::Duration::weeks(1);
+				let basis_date = self.basis.naive_local().date();
+				let mut month = basis_date.with_day(1).unwrap();
 
-					Some(instance)
+				for _ in 0..MONTHLY_SEARCH_LIMIT {
+					match self.specifier.day_in_month(month.year(), month.month()) {
+						Some(date) if date >= basis_date => return Some(self.advance_to(date, specifier_time)),
+						_ => month = first_of_next_month(month),
+					}
 				}
+
+				None
 			}
-			Specifier::Daily { time } => {
-				let specifier_time: chrono::NaiveTime = NaiveTime::parse_from_str(time, "%H:%M")
-					.or_else(|_| NaiveTime::parse_from_str(time, "%H:%M:%S"))
-					.expect("invalid time specifier");
+			Specifier::Recurrence(rule) => {
+				let tz = self.basis.timezone();
+
+				// Like the other patterns, the first instance may be earlier on the same
+				// day as the basis.
+				let instance = if self.started {
+					rule.next_occurrence(&tz, self.basis.naive_local(), false)
+				} else {
+					rule.next_occurrence(&tz, self.basis.naive_local().date().and_hms(0, 0, 0), true)
+				}?;
+
+				let instance = localize(&tz, instance);
+
+				self.started = true;
+				self.basis = instance.to_owned();
+
+				Some(instance)
+			}
+			Specifier::Solar {
+				event,
+				latitude,
+				longitude,
+			} => {
+				let tz = self.basis.timezone();
+				let basis_date = self.basis.naive_local().date();
+
+				let (date, instance) = (0..SOLAR_SEARCH_LIMIT)
+					.map(|offset| basis_date + Duration::days(offset))
+					.find_map(|date| {
+						event
+							.on(date, *latitude, *longitude)
+							.map(|instance| (date, instance))
+					})?;
+
+				self.basis = localize(&tz, date.succ().and_hms(0, 0, 0));
+
+				Some(tz.from_utc_datetime(&instance))
+			}
+			Specifier::Offset { specifier, offset } => {
+				// Start the inner specifier from the equivalently shifted basis, so the
+				// first instance lands relative to the basis as it would unshifted.
+				let basis = self.basis.clone() - *offset;
+				let inner = self
+					.inner
+					.get_or_insert_with(|| Box::new(specifier.instances(&basis)));
+
+				let instance = inner.next()? + *offset;
+
+				self.basis = instance.to_owned();
 
-				let instance = self.basis.date().and_time(specifier_time).unwrap();
+				Some(instance)
+			}
+			Specifier::Bounded {
+				specifier,
+				from,
+				until,
+			} => {
+				let basis = match from {
+					Some(from) if from > &self.basis => from.to_owned(),
+					_ => self.basis.to_owned(),
+				};
+				let inner = self
+					.inner
+					.get_or_insert_with(|| Box::new(specifier.instances(&basis)));
+
+				let instance = inner.find(|instance| from.as_ref().is_none_or(|from| instance >= from))?;
+
+				if until.as_ref().is_some_and(|until| &instance > until) {
+					return None;
+				}
 
-				self.basis = self.basis.to_owned() + chrono::Duration::days(1);
+				self.basis = instance.to_owned();
 
 				Some(instance)
 			}
+			Specifier::Yearly { month, day, time } => {
+				let specifier_time: chrono::NaiveTime = *time;
+
+				let basis_date = self.basis.naive_local().date();
+
+				(basis_date.year()..basis_date.year() + YEARLY_SEARCH_LIMIT)
+					.filter_map(|year| NaiveDate::from_ymd_opt(year, *month, *day))
+					.find(|date| date >= &basis_date)
+					.map(|date| self.advance_to(date, specifier_time))
+			}
+		}
+	}
+}
+
+/// An iterator over the instances of a [`Specifier`] at or before a basis, from
+/// the latest to the earliest
+///
+/// This works by searching forwards through successively earlier windows of
+/// time, so it supports every kind of specifier.
+#[derive(Debug)]
+pub struct InstancesBefore<'iteration, Tz: TimeZone> {
+	specifier: &'iteration Specifier<Tz>,
+	basis: DateTime<Tz>,
+	/// The end of the next window to search, which is only inclusive for the
+	/// first window
+	upper: DateTime<Tz>,
+	inclusive: bool,
+	window: Duration,
+	/// Instances found in the last window, earliest first
+	found: Vec<DateTime<Tz>>,
+}
+
+impl<'iteration, Tz: TimeZone> Iterator for InstancesBefore<'iteration, Tz> {
+	type Item = chrono::DateTime<Tz>;
+
+	fn next(&mut self) -> Option<Self::Item> {
+		// An exact time is its only instance, however long before the basis it is.
+		if let Specifier::Exact(time) = self.specifier {
+			let found = time < &self.upper || (self.inclusive && time == &self.upper);
+
+			self.upper = time.to_owned();
+			self.inclusive = false;
+
+			return Some(time.to_owned()).filter(|_| found);
+		}
+
+		let limit = self.basis.clone() - Duration::days(BACKWARD_SEARCH_LIMIT_DAYS);
+
+		while self.found.is_empty() {
+			if self.upper < limit {
+				return None;
+			}
+
+			let lower = self.upper.clone() - self.window;
+			let upper = self.upper.clone();
+			let inclusive = self.inclusive;
+
+			self.found = self
+				.specifier
+				.instances(&lower)
+				.skip_while(|instance| instance < &lower)
+				.take_while(|instance| instance < &upper || (inclusive && instance == &upper))
+				.collect();
+
+			self.upper = lower;
+			self.inclusive = false;
+			self.window = (self.window * 2).min(Duration::days(BACKWARD_WINDOW_LIMIT_DAYS));
+		}
+
+		self.found.pop()
+	}
+}
+
+impl<Tz: TimeZone> core::str::FromStr for Specifier<Tz> {
+	type Err = SpecifierError;
+
+	/// Parse a specifier such as `Thu 10:15`, `daily 07:00:30`, `Mon-Fri 07:00`
+	/// (see [`WeekdaySet`]) or an RFC 5545 recurrence rule (see
+	/// [`Specifier::from_rrule`])
+	fn from_str(s: &str) -> error::Result<Self> {
+		let s = s.trim();
+
+		if s.starts_with("FREQ=") || s.starts_with("RRULE:") || s.starts_with("DTSTART") {
+			return Self::from_rrule(s);
+		}
+
+		// The time is the last word, and everything before it says which days.
+		let mut words = s.rsplitn(2, char::is_whitespace);
+
+		match (words.next(), words.next().map(str::trim)) {
+			(Some(time), Some(days)) if days.eq_ignore_ascii_case("daily") => Ok(Specifier::Daily {
+				time: parse_time(time)?,
+			}),
+			(Some(time), Some(days)) => match days.parse::<Weekday>() {
+				Ok(day) => Ok(Specifier::Weekly {
+					day,
+					time: parse_time(time)?,
+				}),
+				Err(_) => Ok(Specifier::Weekdays {
+					days: days.parse()?,
+					time: parse_time(time)?,
+				}),
+			},
+			_ => Err(SpecifierError::Unrecognized(s.to_string())),
 		}
 	}
 }
 
 impl<Tz: TimeZone> Specifier<Tz> {
-	pub fn instances(&self, basis: &DateTime<Tz>) -> Instances<Tz> {
+	/// Create a specifier from an RFC 5545 recurrence rule, such as
+	/// `FREQ=WEEKLY;BYDAY=MO,WE,FR;BYHOUR=7;BYMINUTE=30`
+	///
+	/// See [`RRule`] for what is supported.
+	pub fn from_rrule(rule: &str) -> error::Result<Self> {
+		rule.parse().map(Specifier::Recurrence)
+	}
+
+	/// Create a specifier for every `days` days at the given time, in step with
+	/// the anchor date
+	pub fn every_days(anchor: NaiveDate, days: u32, time: NaiveTime) -> Self {
+		Specifier::Interval { anchor, days, time }
+	}
+
+	/// Create a specifier for every `weeks` weeks at the given time, on the
+	/// weekday of the anchor date and in step with it
+	///
+	/// Panics if `weeks` is more than `u32::MAX / 7`, since the interval is kept
+	/// in days.
+	pub fn every_weeks(anchor: NaiveDate, weeks: u32, time: NaiveTime) -> Self {
+		Specifier::Interval {
+			anchor,
+			days: weeks
+				.checked_mul(7)
+				.expect("interval of weeks is too long to count in days"),
+			time,
+		}
+	}
+
+	/// Shift every instance of this specifier by the given offset, which is
+	/// negative for earlier instances
+	pub fn offset_by(self, offset: Duration) -> Self {
+		Specifier::Offset {
+			specifier: Box::new(self),
+			offset,
+		}
+	}
+
+	/// Only produce instances of this specifier at or after the given time
+	pub fn starting(self, start: DateTime<Tz>) -> Self {
+		match self {
+			Specifier::Bounded {
+				specifier, until, ..
+			} => Specifier::Bounded {
+				specifier,
+				from: Some(start),
+				until,
+			},
+			specifier => Specifier::Bounded {
+				specifier: Box::new(specifier),
+				from: Some(start),
+				until: None,
+			},
+		}
+	}
+
+	/// Only produce instances of this specifier at or before the given time
+	pub fn until(self, end: DateTime<Tz>) -> Self {
+		match self {
+			Specifier::Bounded {
+				specifier, from, ..
+			} => Specifier::Bounded {
+				specifier,
+				from,
+				until: Some(end),
+			},
+			specifier => Specifier::Bounded {
+				specifier: Box::new(specifier),
+				from: None,
+				until: Some(end),
+			},
+		}
+	}
+
+	pub fn instances(&self, basis: &DateTime<Tz>) -> Instances<'_, Tz> {
 		let specifier = self;
 		Instances {
 			specifier,
 			basis: basis.to_owned(),
+			started: false,
+			inner: None,
+		}
+	}
+
+	/// Iterate over the instances at or before the basis, starting with the
+	/// latest one
+	///
+	/// Unlike [`Specifier::instances`], which may start with an instance earlier
+	/// on the same day as the basis, this never produces an instance after the
+	/// basis. An instance exactly at the basis is included.
+	pub fn instances_before(&self, basis: &DateTime<Tz>) -> InstancesBefore<'_, Tz> {
+		InstancesBefore {
+			specifier: self,
+			basis: basis.to_owned(),
+			upper: basis.to_owned(),
+			inclusive: true,
+			window: Duration::days(1),
+			found: Vec::new(),
+		}
+	}
+
+	/// Compute the date on which a monthly specifier falls in the given month,
+	/// if it falls in that month at all
+	fn day_in_month(&self, year: i32, month: u32) -> Option<NaiveDate> {
+		match self {
+			Specifier::Monthly { day, .. } => NaiveDate::from_ymd_opt(year, month, *day),
+			Specifier::MonthlyNthWeekday { nth, day, .. } if *nth >= 1 => {
+				let specifier_day: chrono::Weekday = *day;
+				let first = NaiveDate::from_ymd(year, month, 1);
+				let offset =
+					(7 + specifier_day.num_days_from_monday() - first.weekday().num_days_from_monday()) % 7;
+
+				NaiveDate::from_ymd_opt(year, month, 1 + offset + 7 * (nth - 1))
+			}
+			Specifier::MonthlyLastWeekday { day, .. } => {
+				let specifier_day: chrono::Weekday = *day;
+				let last = first_of_next_month(NaiveDate::from_ymd(year, month, 1)).pred();
+				let offset =
+					(7 + last.weekday().num_days_from_monday() - specifier_day.num_days_from_monday()) % 7;
+
+				Some(last - chrono::Duration::days(offset.into()))
+			}
+			_ => None,
+		}
+	}
+
+	/// Determine whether the given instance of this specifier is meant for a
+	/// local time which was skipped when clocks sprang forward, and so was moved
+	/// later by the length of the gap, rather than falling just after the gap
+	pub(crate) fn moved_out_of_gap(&self, instance: &DateTime<Tz>) -> bool {
+		let tz = instance.timezone();
+		let local = instance.naive_local();
+		let moved_from = |intended: NaiveDateTime| {
+			matches!(tz.from_local_datetime(&intended), LocalResult::None)
+				&& &localize(&tz, intended) == instance
+		};
+
+		// A local time is moved later, so it was meant for the same day or, if the
+		// gap crosses midnight, the day before.
+		match self {
+			Specifier::Weekly { time, .. }
+			| Specifier::Daily { time }
+			| Specifier::Weekdays { time, .. }
+			| Specifier::Interval { time, .. }
+			| Specifier::Monthly { time, .. }
+			| Specifier::MonthlyNthWeekday { time, .. }
+			| Specifier::MonthlyLastWeekday { time, .. }
+			| Specifier::Yearly { time, .. } => [local.date(), local.date().pred()]
+				.iter()
+				.any(|date| moved_from(date.and_time(*time))),
+			Specifier::Recurrence(rule) => {
+				let mut from = local.date().pred().and_hms(0, 0, 0);
+				let mut inclusive = true;
+
+				while let Some(occurrence) = rule.next_occurrence(&tz, from, inclusive) {
+					if occurrence > local {
+						break;
+					}
+					if moved_from(occurrence) {
+						return true;
+					}
+
+					from = occurrence;
+					inclusive = false;
+				}
+
+				false
+			}
+			Specifier::Offset { specifier, offset } => {
+				specifier.moved_out_of_gap(&(instance.clone() - *offset))
+			}
+			Specifier::Bounded { specifier, .. } => specifier.moved_out_of_gap(instance),
+			Specifier::Solar { .. } | Specifier::Exact(_) => false,
 		}
 	}
 
-	fn next(&self, n: usize, basis: &DateTime<Tz>) -> Vec<DateTime<Tz>> {
-		self.instances(basis).take(n).collect()
+	/// Find the first instance that falls strictly after the given time
+	pub fn next_after(&self, time: &DateTime<Tz>) -> Option<DateTime<Tz>> {
+		self.instances(time).find(|instance| instance > time)
+	}
+
+	/// Find the last instance that falls at or before the given time
+	pub fn last_at_or_before(&self, time: &DateTime<Tz>) -> Option<DateTime<Tz>> {
+		self.instances_before(time).next()
 	}
 }
 
 #[cfg(test)]
 mod tests {
 	mod specifier {
-		use crate::Specifier;
-		use chrono::DateTime;
+		use crate::{Specifier, SpecifierError, WeekdaySet};
+		use chrono::{DateTime, NaiveDate, NaiveTime, Weekday};
 
 		#[test]
 		fn instances_exact() {
@@ -113,11 +663,33 @@ mod tests {
 			);
 		}
 
+		#[test]
+		fn instances_exact_at_basis() {
+			let now = chrono::Local::now();
+			let s = Specifier::Exact(now);
+			assert_eq!(
+				s.instances(&now).collect::<Vec<DateTime<chrono::Local>>>(),
+				vec![now]
+			);
+		}
+
+		#[test]
+		fn next_after_skips_earlier_instances() {
+			let t_ref = DateTime::parse_from_rfc3339("2020-01-16T10:15:00-05:00").unwrap();
+			let s = Specifier::Daily {
+				time: NaiveTime::from_hms(7, 0, 0),
+			};
+			assert_eq!(
+				s.next_after(&t_ref),
+				Some(DateTime::parse_from_rfc3339("2020-01-17T07:00:00-05:00").unwrap())
+			);
+		}
+
 		#[test]
 		fn instances_daily() {
 			let t_ref = DateTime::parse_from_rfc3339("2020-01-16T10:15:00-05:00").unwrap();
 			let s = Specifier::Daily {
-				time: "07:00".to_string(),
+				time: NaiveTime::from_hms(7, 0, 0),
 			};
 			assert_eq!(
 				s.instances(&t_ref)
@@ -135,8 +707,8 @@ mod tests {
 		fn instances_weekly_start_after_date() {
 			let t_ref = DateTime::parse_from_rfc3339("2020-01-16T10:15:00-05:00").unwrap();
 			let s = Specifier::Weekly {
-				day: "Tuesday".to_string(),
-				time: "07:00".to_string(),
+				day: Weekday::Tue,
+				time: NaiveTime::from_hms(7, 0, 0),
 			};
 			assert_eq!(
 				s.instances(&t_ref)
@@ -154,8 +726,8 @@ mod tests {
 		fn instances_weekly_start_on_same_date() {
 			let t_ref = DateTime::parse_from_rfc3339("2020-01-14T10:15:00-05:00").unwrap();
 			let s = Specifier::Weekly {
-				day: "Tuesday".to_string(),
-				time: "07:00".to_string(),
+				day: Weekday::Tue,
+				time: NaiveTime::from_hms(7, 0, 0),
 			};
 			assert_eq!(
 				s.instances(&t_ref)
@@ -168,5 +740,914 @@ mod tests {
 				]
 			);
 		}
+
+		#[test]
+		fn instances_weekdays() {
+			let t_ref = DateTime::parse_from_rfc3339("2020-01-16T10:15:00-05:00").unwrap();
+			let s = Specifier::Weekdays {
+				days: WeekdaySet::weekdays(),
+				time: NaiveTime::from_hms(7, 0, 0),
+			};
+			assert_eq!(
+				s.instances(&t_ref)
+					.take(4)
+					.collect::<Vec<DateTime<chrono::FixedOffset>>>(),
+				vec![
+					DateTime::parse_from_rfc3339("2020-01-16T07:00:00-05:00").unwrap(),
+					DateTime::parse_from_rfc3339("2020-01-17T07:00:00-05:00").unwrap(),
+					DateTime::parse_from_rfc3339("2020-01-20T07:00:00-05:00").unwrap(),
+					DateTime::parse_from_rfc3339("2020-01-21T07:00:00-05:00").unwrap(),
+				]
+			);
+		}
+
+		#[test]
+		fn instances_weekdays_empty() {
+			let t_ref = DateTime::parse_from_rfc3339("2020-01-16T10:15:00-05:00").unwrap();
+			let s = Specifier::Weekdays {
+				days: WeekdaySet::new(),
+				time: NaiveTime::from_hms(7, 0, 0),
+			};
+			assert_eq!(s.instances(&t_ref).next(), None);
+		}
+
+		#[test]
+		fn instances_every_other_week() {
+			let t_ref = DateTime::parse_from_rfc3339("2020-01-16T10:15:00-05:00").unwrap();
+			let s = Specifier::every_weeks(
+				NaiveDate::from_ymd(2020, 1, 7),
+				2,
+				NaiveTime::from_hms(9, 0, 0),
+			);
+			assert_eq!(
+				s.instances(&t_ref)
+					.take(3)
+					.collect::<Vec<DateTime<chrono::FixedOffset>>>(),
+				vec![
+					DateTime::parse_from_rfc3339("2020-01-21T09:00:00-05:00").unwrap(),
+					DateTime::parse_from_rfc3339("2020-02-04T09:00:00-05:00").unwrap(),
+					DateTime::parse_from_rfc3339("2020-02-18T09:00:00-05:00").unwrap(),
+				]
+			);
+		}
+
+		#[test]
+		fn every_weeks_longest_interval() {
+			let anchor = NaiveDate::from_ymd(2020, 1, 7);
+			let time = NaiveTime::from_hms(9, 0, 0);
+			assert_eq!(
+				Specifier::<chrono::FixedOffset>::every_weeks(anchor, u32::MAX / 7, time),
+				Specifier::Interval {
+					anchor,
+					days: u32::MAX / 7 * 7,
+					time,
+				}
+			);
+		}
+
+		#[test]
+		#[should_panic(expected = "too long")]
+		fn every_weeks_too_long_interval() {
+			let _: Specifier<chrono::FixedOffset> = Specifier::every_weeks(
+				NaiveDate::from_ymd(2020, 1, 7),
+				u32::MAX / 7 + 1,
+				NaiveTime::from_hms(9, 0, 0),
+			);
+		}
+
+		#[test]
+		fn instances_every_third_day_start_on_same_date() {
+			let t_ref = DateTime::parse_from_rfc3339("2020-01-16T10:15:00-05:00").unwrap();
+			let s = Specifier::every_days(
+				NaiveDate::from_ymd(2020, 1, 10),
+				3,
+				NaiveTime::from_hms(7, 0, 0),
+			);
+			assert_eq!(
+				s.instances(&t_ref)
+					.take(3)
+					.collect::<Vec<DateTime<chrono::FixedOffset>>>(),
+				vec![
+					DateTime::parse_from_rfc3339("2020-01-16T07:00:00-05:00").unwrap(),
+					DateTime::parse_from_rfc3339("2020-01-19T07:00:00-05:00").unwrap(),
+					DateTime::parse_from_rfc3339("2020-01-22T07:00:00-05:00").unwrap(),
+				]
+			);
+		}
+
+		#[test]
+		fn instances_interval_before_anchor() {
+			let t_ref = DateTime::parse_from_rfc3339("2020-01-01T10:15:00-05:00").unwrap();
+			let s = Specifier::every_days(
+				NaiveDate::from_ymd(2020, 1, 10),
+				3,
+				NaiveTime::from_hms(7, 0, 0),
+			);
+			assert_eq!(
+				s.instances(&t_ref).next(),
+				Some(DateTime::parse_from_rfc3339("2020-01-01T07:00:00-05:00").unwrap())
+			);
+		}
+
+		#[test]
+		fn instances_monthly_start_before_day() {
+			let t_ref = DateTime::parse_from_rfc3339("2020-01-14T10:15:00-05:00").unwrap();
+			let s = Specifier::Monthly {
+				day: 15,
+				time: NaiveTime::from_hms(10, 0, 0),
+			};
+			assert_eq!(
+				s.instances(&t_ref)
+					.take(3)
+					.collect::<Vec<DateTime<chrono::FixedOffset>>>(),
+				vec![
+					DateTime::parse_from_rfc3339("2020-01-15T10:00:00-05:00").unwrap(),
+					DateTime::parse_from_rfc3339("2020-02-15T10:00:00-05:00").unwrap(),
+					DateTime::parse_from_rfc3339("2020-03-15T10:00:00-05:00").unwrap(),
+				]
+			);
+		}
+
+		#[test]
+		fn instances_monthly_start_on_same_date() {
+			let t_ref = DateTime::parse_from_rfc3339("2020-01-15T10:15:00-05:00").unwrap();
+			let s = Specifier::Monthly {
+				day: 15,
+				time: NaiveTime::from_hms(7, 0, 0),
+			};
+			assert_eq!(
+				s.instances(&t_ref)
+					.take(2)
+					.collect::<Vec<DateTime<chrono::FixedOffset>>>(),
+				vec![
+					DateTime::parse_from_rfc3339("2020-01-15T07:00:00-05:00").unwrap(),
+					DateTime::parse_from_rfc3339("2020-02-15T07:00:00-05:00").unwrap(),
+				]
+			);
+		}
+
+		#[test]
+		fn instances_monthly_skips_short_months() {
+			let t_ref = DateTime::parse_from_rfc3339("2020-01-16T10:15:00-05:00").unwrap();
+			let s = Specifier::Monthly {
+				day: 31,
+				time: NaiveTime::from_hms(7, 0, 0),
+			};
+			assert_eq!(
+				s.instances(&t_ref)
+					.take(3)
+					.collect::<Vec<DateTime<chrono::FixedOffset>>>(),
+				vec![
+					DateTime::parse_from_rfc3339("2020-01-31T07:00:00-05:00").unwrap(),
+					DateTime::parse_from_rfc3339("2020-03-31T07:00:00-05:00").unwrap(),
+					DateTime::parse_from_rfc3339("2020-05-31T07:00:00-05:00").unwrap(),
+				]
+			);
+		}
+
+		#[test]
+		fn instances_monthly_nth_weekday() {
+			let t_ref = DateTime::parse_from_rfc3339("2020-01-16T10:15:00-05:00").unwrap();
+			let s = Specifier::MonthlyNthWeekday {
+				nth: 1,
+				day: Weekday::Mon,
+				time: NaiveTime::from_hms(10, 0, 0),
+			};
+			assert_eq!(
+				s.instances(&t_ref)
+					.take(3)
+					.collect::<Vec<DateTime<chrono::FixedOffset>>>(),
+				vec![
+					DateTime::parse_from_rfc3339("2020-02-03T10:00:00-05:00").unwrap(),
+					DateTime::parse_from_rfc3339("2020-03-02T10:00:00-05:00").unwrap(),
+					DateTime::parse_from_rfc3339("2020-04-06T10:00:00-05:00").unwrap(),
+				]
+			);
+		}
+
+		#[test]
+		fn instances_monthly_fifth_weekday_skips_months() {
+			let t_ref = DateTime::parse_from_rfc3339("2020-01-01T00:00:00-05:00").unwrap();
+			let s = Specifier::MonthlyNthWeekday {
+				nth: 5,
+				day: Weekday::Fri,
+				time: NaiveTime::from_hms(10, 0, 0),
+			};
+			assert_eq!(
+				s.instances(&t_ref)
+					.take(3)
+					.collect::<Vec<DateTime<chrono::FixedOffset>>>(),
+				vec![
+					DateTime::parse_from_rfc3339("2020-01-31T10:00:00-05:00").unwrap(),
+					DateTime::parse_from_rfc3339("2020-05-29T10:00:00-05:00").unwrap(),
+					DateTime::parse_from_rfc3339("2020-07-31T10:00:00-05:00").unwrap(),
+				]
+			);
+		}
+
+		#[test]
+		fn instances_monthly_last_weekday() {
+			let t_ref = DateTime::parse_from_rfc3339("2020-01-16T10:15:00-05:00").unwrap();
+			let s = Specifier::MonthlyLastWeekday {
+				day: Weekday::Fri,
+				time: NaiveTime::from_hms(17, 0, 0),
+			};
+			assert_eq!(
+				s.instances(&t_ref)
+					.take(3)
+					.collect::<Vec<DateTime<chrono::FixedOffset>>>(),
+				vec![
+					DateTime::parse_from_rfc3339("2020-01-31T17:00:00-05:00").unwrap(),
+					DateTime::parse_from_rfc3339("2020-02-28T17:00:00-05:00").unwrap(),
+					DateTime::parse_from_rfc3339("2020-03-27T17:00:00-05:00").unwrap(),
+				]
+			);
+		}
+
+		#[test]
+		fn instances_monthly_invalid_day_is_empty() {
+			let t_ref = DateTime::parse_from_rfc3339("2020-01-16T10:15:00-05:00").unwrap();
+			let s = Specifier::Monthly {
+				day: 32,
+				time: NaiveTime::from_hms(7, 0, 0),
+			};
+			assert_eq!(s.instances(&t_ref).next(), None);
+		}
+
+		#[test]
+		fn instances_yearly() {
+			let t_ref = DateTime::parse_from_rfc3339("2020-01-16T10:15:00-05:00").unwrap();
+			let s = Specifier::Yearly {
+				month: 12,
+				day: 25,
+				time: NaiveTime::from_hms(0, 0, 0),
+			};
+			assert_eq!(
+				s.instances(&t_ref)
+					.take(3)
+					.collect::<Vec<DateTime<chrono::FixedOffset>>>(),
+				vec![
+					DateTime::parse_from_rfc3339("2020-12-25T00:00:00-05:00").unwrap(),
+					DateTime::parse_from_rfc3339("2021-12-25T00:00:00-05:00").unwrap(),
+					DateTime::parse_from_rfc3339("2022-12-25T00:00:00-05:00").unwrap(),
+				]
+			);
+		}
+
+		#[test]
+		fn instances_yearly_start_on_same_date() {
+			let t_ref = DateTime::parse_from_rfc3339("2020-07-04T10:15:00-05:00").unwrap();
+			let s = Specifier::Yearly {
+				month: 7,
+				day: 4,
+				time: NaiveTime::from_hms(7, 0, 0),
+			};
+			assert_eq!(
+				s.instances(&t_ref)
+					.take(2)
+					.collect::<Vec<DateTime<chrono::FixedOffset>>>(),
+				vec![
+					DateTime::parse_from_rfc3339("2020-07-04T07:00:00-05:00").unwrap(),
+					DateTime::parse_from_rfc3339("2021-07-04T07:00:00-05:00").unwrap(),
+				]
+			);
+		}
+
+		#[test]
+		fn instances_yearly_leap_day_skips_common_years() {
+			let t_ref = DateTime::parse_from_rfc3339("2020-03-01T10:15:00-05:00").unwrap();
+			let s = Specifier::Yearly {
+				month: 2,
+				day: 29,
+				time: NaiveTime::from_hms(7, 0, 0),
+			};
+			assert_eq!(
+				s.instances(&t_ref)
+					.take(2)
+					.collect::<Vec<DateTime<chrono::FixedOffset>>>(),
+				vec![
+					DateTime::parse_from_rfc3339("2024-02-29T07:00:00-05:00").unwrap(),
+					DateTime::parse_from_rfc3339("2028-02-29T07:00:00-05:00").unwrap(),
+				]
+			);
+		}
+
+		#[test]
+		fn instances_rrule_weekly_by_day() {
+			let t_ref = DateTime::parse_from_rfc3339("2020-01-16T10:15:00-05:00").unwrap();
+			let s = Specifier::from_rrule("FREQ=WEEKLY;BYDAY=MO,WE,FR;BYHOUR=7;BYMINUTE=30").unwrap();
+			assert_eq!(
+				s.instances(&t_ref)
+					.take(4)
+					.collect::<Vec<DateTime<chrono::FixedOffset>>>(),
+				vec![
+					DateTime::parse_from_rfc3339("2020-01-17T07:30:00-05:00").unwrap(),
+					DateTime::parse_from_rfc3339("2020-01-20T07:30:00-05:00").unwrap(),
+					DateTime::parse_from_rfc3339("2020-01-22T07:30:00-05:00").unwrap(),
+					DateTime::parse_from_rfc3339("2020-01-24T07:30:00-05:00").unwrap(),
+				]
+			);
+		}
+
+		#[test]
+		fn instances_rrule_start_on_same_date() {
+			let t_ref = DateTime::parse_from_rfc3339("2020-01-16T10:15:00-05:00").unwrap();
+			let s = Specifier::from_rrule("RRULE:FREQ=DAILY;BYHOUR=7,19").unwrap();
+			assert_eq!(
+				s.instances(&t_ref)
+					.take(3)
+					.collect::<Vec<DateTime<chrono::FixedOffset>>>(),
+				vec![
+					DateTime::parse_from_rfc3339("2020-01-16T07:00:00-05:00").unwrap(),
+					DateTime::parse_from_rfc3339("2020-01-16T19:00:00-05:00").unwrap(),
+					DateTime::parse_from_rfc3339("2020-01-17T07:00:00-05:00").unwrap(),
+				]
+			);
+		}
+
+		#[test]
+		fn instances_rrule_interval() {
+			let t_ref = DateTime::parse_from_rfc3339("2020-01-16T10:15:00-05:00").unwrap();
+			let s =
+				Specifier::from_rrule("DTSTART:20200107T090000\nRRULE:FREQ=WEEKLY;INTERVAL=2").unwrap();
+			assert_eq!(
+				s.instances(&t_ref)
+					.take(3)
+					.collect::<Vec<DateTime<chrono::FixedOffset>>>(),
+				vec![
+					DateTime::parse_from_rfc3339("2020-01-21T09:00:00-05:00").unwrap(),
+					DateTime::parse_from_rfc3339("2020-02-04T09:00:00-05:00").unwrap(),
+					DateTime::parse_from_rfc3339("2020-02-18T09:00:00-05:00").unwrap(),
+				]
+			);
+		}
+
+		#[test]
+		fn instances_rrule_count() {
+			let t_ref = DateTime::parse_from_rfc3339("2020-01-16T10:15:00-05:00").unwrap();
+			let s = Specifier::from_rrule("DTSTART:20200114T070000\nRRULE:FREQ=DAILY;COUNT=4").unwrap();
+			assert_eq!(
+				s.instances(&t_ref)
+					.collect::<Vec<DateTime<chrono::FixedOffset>>>(),
+				vec![
+					DateTime::parse_from_rfc3339("2020-01-16T07:00:00-05:00").unwrap(),
+					DateTime::parse_from_rfc3339("2020-01-17T07:00:00-05:00").unwrap(),
+				]
+			);
+		}
+
+		#[test]
+		fn instances_rrule_until() {
+			let t_ref = DateTime::parse_from_rfc3339("2020-04-25T10:15:00-05:00").unwrap();
+			let s = Specifier::from_rrule("FREQ=WEEKLY;BYDAY=TU;BYHOUR=7;UNTIL=20200505").unwrap();
+			assert_eq!(
+				s.instances(&t_ref)
+					.collect::<Vec<DateTime<chrono::FixedOffset>>>(),
+				vec![
+					DateTime::parse_from_rfc3339("2020-04-28T07:00:00-05:00").unwrap(),
+					DateTime::parse_from_rfc3339("2020-05-05T07:00:00-05:00").unwrap(),
+				]
+			);
+		}
+
+		#[test]
+		fn instances_rrule_monthly_ordinal_by_day() {
+			let t_ref = DateTime::parse_from_rfc3339("2020-01-16T10:15:00-05:00").unwrap();
+			let s = Specifier::from_rrule("FREQ=MONTHLY;BYDAY=1MO,-1FR;BYHOUR=10").unwrap();
+			assert_eq!(
+				s.instances(&t_ref)
+					.take(3)
+					.collect::<Vec<DateTime<chrono::FixedOffset>>>(),
+				vec![
+					DateTime::parse_from_rfc3339("2020-01-31T10:00:00-05:00").unwrap(),
+					DateTime::parse_from_rfc3339("2020-02-03T10:00:00-05:00").unwrap(),
+					DateTime::parse_from_rfc3339("2020-02-28T10:00:00-05:00").unwrap(),
+				]
+			);
+		}
+
+		#[test]
+		fn instances_rrule_yearly_by_month_and_month_day() {
+			let t_ref = DateTime::parse_from_rfc3339("2020-01-16T10:15:00-05:00").unwrap();
+			let s = Specifier::from_rrule("FREQ=YEARLY;BYMONTH=2,3;BYMONTHDAY=-1").unwrap();
+			assert_eq!(
+				s.instances(&t_ref)
+					.take(3)
+					.collect::<Vec<DateTime<chrono::FixedOffset>>>(),
+				vec![
+					DateTime::parse_from_rfc3339("2020-02-29T00:00:00-05:00").unwrap(),
+					DateTime::parse_from_rfc3339("2020-03-31T00:00:00-05:00").unwrap(),
+					DateTime::parse_from_rfc3339("2021-02-28T00:00:00-05:00").unwrap(),
+				]
+			);
+		}
+
+		#[test]
+		fn parse_weekly() {
+			assert_eq!(
+				"Thu 10:15".parse::<Specifier<chrono::FixedOffset>>(),
+				Ok(Specifier::Weekly {
+					day: Weekday::Thu,
+					time: NaiveTime::from_hms(10, 15, 0),
+				})
+			);
+			assert_eq!(
+				"tuesday 07:00:30".parse::<Specifier<chrono::FixedOffset>>(),
+				Ok(Specifier::Weekly {
+					day: Weekday::Tue,
+					time: NaiveTime::from_hms(7, 0, 30),
+				})
+			);
+		}
+
+		#[test]
+		fn parse_weekdays() {
+			let weekdays = |days| Specifier::Weekdays {
+				days,
+				time: NaiveTime::from_hms(7, 0, 0),
+			};
+			assert_eq!(
+				"Mon-Fri 07:00".parse::<Specifier<chrono::FixedOffset>>(),
+				Ok(weekdays(WeekdaySet::weekdays()))
+			);
+			assert_eq!(
+				"weekdays 07:00".parse::<Specifier<chrono::FixedOffset>>(),
+				Ok(weekdays(WeekdaySet::weekdays()))
+			);
+			assert_eq!(
+				"Sat, Sun 07:00".parse::<Specifier<chrono::FixedOffset>>(),
+				Ok(weekdays(WeekdaySet::weekends()))
+			);
+			assert_eq!(
+				"Fri-Mon 07:00".parse::<Specifier<chrono::FixedOffset>>(),
+				Ok(weekdays(
+					vec![Weekday::Fri, Weekday::Sat, Weekday::Sun, Weekday::Mon]
+						.into_iter()
+						.collect()
+				))
+			);
+			assert_eq!(
+				"Mon-Fro 07:00".parse::<Specifier<chrono::FixedOffset>>(),
+				Err(SpecifierError::InvalidDay("Mon-Fro".to_string()))
+			);
+		}
+
+		#[test]
+		fn parse_daily() {
+			assert_eq!(
+				"daily 07:00".parse::<Specifier<chrono::FixedOffset>>(),
+				Ok(Specifier::Daily {
+					time: NaiveTime::from_hms(7, 0, 0),
+				})
+			);
+		}
+
+		#[test]
+		fn parse_rrule() {
+			assert_eq!(
+				"FREQ=DAILY;BYHOUR=7".parse::<Specifier<chrono::FixedOffset>>(),
+				Specifier::from_rrule("FREQ=DAILY;BYHOUR=7")
+			);
+		}
+
+		#[test]
+		fn parse_invalid() {
+			assert_eq!(
+				"Thursday".parse::<Specifier<chrono::FixedOffset>>(),
+				Err(SpecifierError::Unrecognized("Thursday".to_string()))
+			);
+			assert_eq!(
+				"Thorsday 10:15".parse::<Specifier<chrono::FixedOffset>>(),
+				Err(SpecifierError::InvalidDay("Thorsday".to_string()))
+			);
+			assert_eq!(
+				"daily 25:00".parse::<Specifier<chrono::FixedOffset>>(),
+				Err(SpecifierError::InvalidTime("25:00".to_string()))
+			);
+		}
+
+		#[test]
+		fn instances_offset_before() {
+			let t_ref = DateTime::parse_from_rfc3339("2020-01-16T10:15:00-05:00").unwrap();
+			let s = Specifier::Daily {
+				time: NaiveTime::from_hms(17, 0, 0),
+			}
+			.offset_by(chrono::Duration::minutes(-30));
+			assert_eq!(
+				s.instances(&t_ref)
+					.take(2)
+					.collect::<Vec<DateTime<chrono::FixedOffset>>>(),
+				vec![
+					DateTime::parse_from_rfc3339("2020-01-16T16:30:00-05:00").unwrap(),
+					DateTime::parse_from_rfc3339("2020-01-17T16:30:00-05:00").unwrap(),
+				]
+			);
+		}
+
+		#[test]
+		fn instances_offset_across_midnight() {
+			let t_ref = DateTime::parse_from_rfc3339("2020-01-16T10:15:00-05:00").unwrap();
+			let s = Specifier::Weekly {
+				day: Weekday::Fri,
+				time: NaiveTime::from_hms(23, 45, 0),
+			}
+			.offset_by(chrono::Duration::minutes(30));
+			assert_eq!(
+				s.instances(&t_ref)
+					.take(2)
+					.collect::<Vec<DateTime<chrono::FixedOffset>>>(),
+				vec![
+					DateTime::parse_from_rfc3339("2020-01-18T00:15:00-05:00").unwrap(),
+					DateTime::parse_from_rfc3339("2020-01-25T00:15:00-05:00").unwrap(),
+				]
+			);
+		}
+
+		#[test]
+		fn next_after_offset() {
+			let t_ref = DateTime::parse_from_rfc3339("2020-01-16T16:45:00-05:00").unwrap();
+			let s = Specifier::Daily {
+				time: NaiveTime::from_hms(7, 0, 0),
+			}
+			.offset_by(chrono::Duration::minutes(15));
+			assert_eq!(
+				s.next_after(&t_ref),
+				Some(DateTime::parse_from_rfc3339("2020-01-17T07:15:00-05:00").unwrap())
+			);
+		}
+
+		#[test]
+		fn instances_bounded() {
+			let t_ref = DateTime::parse_from_rfc3339("2020-01-01T10:15:00-05:00").unwrap();
+			let s = Specifier::Weekly {
+				day: Weekday::Tue,
+				time: NaiveTime::from_hms(7, 0, 0),
+			}
+			.starting(DateTime::parse_from_rfc3339("2020-04-14T00:00:00-05:00").unwrap())
+			.until(DateTime::parse_from_rfc3339("2020-05-05T23:59:59-05:00").unwrap());
+			assert_eq!(
+				s.instances(&t_ref)
+					.collect::<Vec<DateTime<chrono::FixedOffset>>>(),
+				vec![
+					DateTime::parse_from_rfc3339("2020-04-14T07:00:00-05:00").unwrap(),
+					DateTime::parse_from_rfc3339("2020-04-21T07:00:00-05:00").unwrap(),
+					DateTime::parse_from_rfc3339("2020-04-28T07:00:00-05:00").unwrap(),
+					DateTime::parse_from_rfc3339("2020-05-05T07:00:00-05:00").unwrap(),
+				]
+			);
+		}
+
+		#[test]
+		fn instances_bounded_start_is_inclusive() {
+			let t_ref = DateTime::parse_from_rfc3339("2020-01-01T10:15:00-05:00").unwrap();
+			let s = Specifier::Daily {
+				time: NaiveTime::from_hms(7, 0, 0),
+			}
+			.starting(DateTime::parse_from_rfc3339("2020-01-14T07:00:00-05:00").unwrap());
+			assert_eq!(
+				s.instances(&t_ref).next(),
+				Some(DateTime::parse_from_rfc3339("2020-01-14T07:00:00-05:00").unwrap())
+			);
+		}
+
+		#[test]
+		fn instances_bounded_after_end_is_empty() {
+			let t_ref = DateTime::parse_from_rfc3339("2020-06-01T10:15:00-05:00").unwrap();
+			let s = Specifier::Daily {
+				time: NaiveTime::from_hms(7, 0, 0),
+			}
+			.until(DateTime::parse_from_rfc3339("2020-05-05T23:59:59-05:00").unwrap());
+			assert_eq!(s.instances(&t_ref).next(), None);
+			assert_eq!(
+				s.last_at_or_before(&t_ref),
+				Some(DateTime::parse_from_rfc3339("2020-05-05T07:00:00-05:00").unwrap())
+			);
+		}
+
+		mod instances_before {
+			use crate::Specifier;
+			use chrono::{DateTime, FixedOffset, NaiveDate, NaiveTime, Weekday};
+
+			fn at(time: &str) -> DateTime<FixedOffset> {
+				DateTime::parse_from_rfc3339(time).unwrap()
+			}
+
+			#[test]
+			fn weekly() {
+				let s = Specifier::Weekly {
+					day: Weekday::Tue,
+					time: NaiveTime::from_hms(7, 0, 0),
+				};
+				assert_eq!(
+					s.instances_before(&at("2020-01-16T10:15:00-05:00"))
+						.take(3)
+						.collect::<Vec<_>>(),
+					vec![
+						at("2020-01-14T07:00:00-05:00"),
+						at("2020-01-07T07:00:00-05:00"),
+						at("2019-12-31T07:00:00-05:00"),
+					]
+				);
+			}
+
+			#[test]
+			fn daily_excludes_later_same_day() {
+				let s = Specifier::Daily {
+					time: NaiveTime::from_hms(17, 0, 0),
+				};
+				assert_eq!(
+					s.instances_before(&at("2020-01-16T10:15:00-05:00"))
+						.take(2)
+						.collect::<Vec<_>>(),
+					vec![
+						at("2020-01-15T17:00:00-05:00"),
+						at("2020-01-14T17:00:00-05:00")
+					]
+				);
+			}
+
+			#[test]
+			fn includes_instance_at_basis() {
+				let s = Specifier::Daily {
+					time: NaiveTime::from_hms(7, 0, 0),
+				};
+				assert_eq!(
+					s.instances_before(&at("2020-01-16T07:00:00-05:00")).next(),
+					Some(at("2020-01-16T07:00:00-05:00"))
+				);
+				assert_eq!(
+					s.last_at_or_before(&at("2020-01-16T06:59:59-05:00")),
+					Some(at("2020-01-15T07:00:00-05:00"))
+				);
+			}
+
+			#[test]
+			fn exact() {
+				let s = Specifier::Exact(at("2020-01-16T07:00:00-05:00"));
+				assert_eq!(
+					s.instances_before(&at("2020-01-16T07:00:00-05:00"))
+						.collect::<Vec<_>>(),
+					vec![at("2020-01-16T07:00:00-05:00")]
+				);
+				assert_eq!(
+					s.instances_before(&at("2020-01-16T06:59:59-05:00")).next(),
+					None
+				);
+			}
+
+			#[test]
+			fn exact_long_before_basis() {
+				let s = Specifier::Exact(at("2019-12-20T00:00:00-06:00"));
+				assert_eq!(
+					s.last_at_or_before(&at("2049-06-20T12:00:00-05:00")),
+					Some(at("2019-12-20T00:00:00-06:00"))
+				);
+			}
+
+			#[test]
+			fn yearly_leap_day() {
+				let s = Specifier::Yearly {
+					month: 2,
+					day: 29,
+					time: NaiveTime::from_hms(7, 0, 0),
+				};
+				assert_eq!(
+					s.instances_before(&at("2023-01-01T00:00:00-05:00"))
+						.take(2)
+						.collect::<Vec<_>>(),
+					vec![
+						at("2020-02-29T07:00:00-05:00"),
+						at("2016-02-29T07:00:00-05:00")
+					]
+				);
+			}
+
+			#[test]
+			fn interval() {
+				let s = Specifier::every_weeks(
+					NaiveDate::from_ymd(2020, 1, 7),
+					2,
+					NaiveTime::from_hms(9, 0, 0),
+				);
+				assert_eq!(
+					s.instances_before(&at("2020-01-16T10:15:00-05:00"))
+						.take(2)
+						.collect::<Vec<_>>(),
+					vec![
+						at("2020-01-07T09:00:00-05:00"),
+						at("2019-12-24T09:00:00-05:00")
+					]
+				);
+			}
+
+			#[test]
+			fn rrule_with_count_runs_out() {
+				let s = Specifier::from_rrule("DTSTART:20200114T070000\nRRULE:FREQ=DAILY;COUNT=2").unwrap();
+				assert_eq!(
+					s.instances_before(&at("2020-01-20T00:00:00-05:00"))
+						.collect::<Vec<_>>(),
+					vec![
+						at("2020-01-15T07:00:00-05:00"),
+						at("2020-01-14T07:00:00-05:00")
+					]
+				);
+			}
+		}
+
+		mod solar {
+			use crate::{SolarEvent, Specifier};
+			use chrono::{DateTime, Duration, TimeZone};
+			use chrono_tz::{America::Chicago, Europe::Oslo};
+
+			fn assert_near<Tz: TimeZone>(actual: DateTime<Tz>, expected: DateTime<Tz>) {
+				assert!(
+					(actual.clone() - expected.clone()).num_seconds().abs() <= 120,
+					"{:?} is not within two minutes of {:?}",
+					actual,
+					expected
+				);
+			}
+
+			fn chicago(event: SolarEvent) -> Specifier<chrono_tz::Tz> {
+				Specifier::Solar {
+					event,
+					latitude: 41.8781,
+					longitude: -87.6298,
+				}
+			}
+
+			#[test]
+			fn instances_sunrise_and_sunset() {
+				let t_ref = Chicago.ymd(2020, 6, 20).and_hms(12, 0, 0);
+
+				let sunrises: Vec<_> = chicago(SolarEvent::Sunrise)
+					.instances(&t_ref)
+					.take(2)
+					.collect();
+				assert_near(sunrises[0], Chicago.ymd(2020, 6, 20).and_hms(5, 15, 0));
+				assert_near(sunrises[1], Chicago.ymd(2020, 6, 21).and_hms(5, 15, 0));
+
+				let sunset = chicago(SolarEvent::Sunset)
+					.instances(&t_ref)
+					.next()
+					.unwrap();
+				assert_near(sunset, Chicago.ymd(2020, 6, 20).and_hms(20, 29, 0));
+			}
+
+			#[test]
+			fn instances_civil_twilight() {
+				let t_ref = Chicago.ymd(2020, 12, 21).and_hms(12, 0, 0);
+
+				let dawn = chicago(SolarEvent::CivilDawn)
+					.instances(&t_ref)
+					.next()
+					.unwrap();
+				assert_near(dawn, Chicago.ymd(2020, 12, 21).and_hms(6, 45, 0));
+
+				let dusk = chicago(SolarEvent::CivilDusk)
+					.instances(&t_ref)
+					.next()
+					.unwrap();
+				assert_near(dusk, Chicago.ymd(2020, 12, 21).and_hms(16, 54, 0));
+			}
+
+			#[test]
+			fn instances_skip_midnight_sun() {
+				let t_ref = Oslo.ymd(2020, 6, 1).and_hms(12, 0, 0);
+				let s = Specifier::Solar {
+					event: SolarEvent::Sunset,
+					latitude: 69.6492,
+					longitude: 18.9553,
+				};
+
+				let sunset = s.instances(&t_ref).next().unwrap();
+				assert!(sunset > Oslo.ymd(2020, 7, 25).and_hms(0, 0, 0));
+				assert!(sunset < Oslo.ymd(2020, 7, 28).and_hms(0, 0, 0));
+				assert!(sunset - t_ref > Duration::days(45));
+			}
+		}
+
+		mod daylight_saving_time {
+			use crate::Specifier;
+			use chrono::{DateTime, NaiveTime, TimeZone, Weekday};
+			use chrono_tz::{America::Chicago, Tz};
+
+			fn local_times(instances: Vec<DateTime<Tz>>) -> Vec<String> {
+				instances
+					.iter()
+					.map(|instance| instance.format("%Y-%m-%d %H:%M %Z").to_string())
+					.collect()
+			}
+
+			#[test]
+			fn daily_keeps_local_time_across_spring_forward() {
+				let t_ref = Chicago.ymd(2020, 3, 7).and_hms(12, 0, 0);
+				let s = Specifier::Daily {
+					time: NaiveTime::from_hms(7, 0, 0),
+				};
+				assert_eq!(
+					local_times(s.instances(&t_ref).take(3).collect()),
+					vec![
+						"2020-03-07 07:00 CST",
+						"2020-03-08 07:00 CDT",
+						"2020-03-09 07:00 CDT"
+					]
+				);
+			}
+
+			#[test]
+			fn daily_keeps_local_time_across_fall_back() {
+				let t_ref = Chicago.ymd(2020, 10, 31).and_hms(12, 0, 0);
+				let s = Specifier::Daily {
+					time: NaiveTime::from_hms(7, 0, 0),
+				};
+				assert_eq!(
+					local_times(s.instances(&t_ref).take(3).collect()),
+					vec![
+						"2020-10-31 07:00 CDT",
+						"2020-11-01 07:00 CST",
+						"2020-11-02 07:00 CST"
+					]
+				);
+			}
+
+			#[test]
+			fn weekly_in_gap_moves_later() {
+				let t_ref = Chicago.ymd(2020, 3, 1).and_hms(0, 0, 0);
+				let s = Specifier::Weekly {
+					day: Weekday::Sun,
+					time: NaiveTime::from_hms(2, 30, 0),
+				};
+				assert_eq!(
+					local_times(s.instances(&t_ref).take(3).collect()),
+					vec![
+						"2020-03-01 02:30 CST",
+						"2020-03-08 03:30 CDT",
+						"2020-03-15 02:30 CDT"
+					]
+				);
+			}
+
+			#[test]
+			fn moved_out_of_gap_only_for_times_in_gap() {
+				let three = Chicago.ymd(2020, 3, 8).and_hms(3, 0, 0);
+				let daily = |hour: u32| Specifier::Daily {
+					time: NaiveTime::from_hms(hour, 0, 0),
+				};
+
+				assert!(daily(2).moved_out_of_gap(&three));
+				assert!(!daily(3).moved_out_of_gap(&three));
+				assert!(!daily(1)
+					.offset_by(chrono::Duration::hours(1))
+					.moved_out_of_gap(&three));
+				assert!(Specifier::from_rrule("FREQ=DAILY;BYHOUR=2;BYMINUTE=0")
+					.unwrap()
+					.moved_out_of_gap(&three));
+				assert!(!daily(2).moved_out_of_gap(&Chicago.ymd(2020, 3, 9).and_hms(3, 0, 0)));
+			}
+
+			#[test]
+			fn daily_in_overlap_is_earlier() {
+				let t_ref = Chicago.ymd(2020, 10, 31).and_hms(12, 0, 0);
+				let s = Specifier::Daily {
+					time: NaiveTime::from_hms(1, 30, 0),
+				};
+				assert_eq!(
+					local_times(s.instances(&t_ref).take(3).collect()),
+					vec![
+						"2020-10-31 01:30 CDT",
+						"2020-11-01 01:30 CDT",
+						"2020-11-02 01:30 CST"
+					]
+				);
+			}
+
+			#[test]
+			fn weekly_from_just_after_midnight_across_fall_back() {
+				let t_ref = Chicago.ymd(2020, 10, 25).and_hms(0, 30, 0);
+				let s = Specifier::Weekly {
+					day: Weekday::Sun,
+					time: NaiveTime::from_hms(0, 15, 0),
+				};
+				assert_eq!(
+					local_times(s.instances(&t_ref).take(2).collect()),
+					vec!["2020-10-25 00:15 CDT", "2020-11-01 00:15 CDT"]
+				);
+			}
+
+			#[test]
+			fn rrule_in_gap_moves_later() {
+				let t_ref = Chicago.ymd(2020, 3, 7).and_hms(12, 0, 0);
+				let s = Specifier::from_rrule("FREQ=DAILY;BYHOUR=2;BYMINUTE=15").unwrap();
+				assert_eq!(
+					local_times(s.instances(&t_ref).take(3).collect()),
+					vec![
+						"2020-03-07 02:15 CST",
+						"2020-03-08 03:15 CDT",
+						"2020-03-09 02:15 CDT"
+					]
+				);
+			}
+		}
 	}
 }
@@ -0,0 +1,40 @@
+#[derive(Clone, Debug, PartialEq)]
+pub enum SpecifierError {
+	/// A specifier which doesn't look like any known form
+	Unrecognized(String),
+	InvalidDay(String),
+	InvalidTime(String),
+	/// A recurrence rule part (or value of one) which is valid RFC 5545 but is
+	/// not supported, such as `FREQ=HOURLY` or `BYSETPOS`
+	Unsupported(String),
+	/// A recurrence rule part whose value couldn't be understood
+	InvalidValue {
+		name: String,
+		value: String,
+	},
+	MissingFrequency,
+	/// The rule needs a `DTSTART` to be computed, e.g. because it has an
+	/// `INTERVAL` or a `COUNT`
+	MissingStart,
+	/// `COUNT` and `UNTIL` were both given, which RFC 5545 forbids
+	CountAndUntil,
+}
+
+impl core::fmt::Display for SpecifierError {
+	fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+		match self {
+			Self::Unrecognized(s) => write!(f, "unrecognized specifier {:?}", s),
+			Self::InvalidDay(day) => write!(f, "invalid day {:?}", day),
+			Self::InvalidTime(time) => write!(f, "invalid time {:?}", time),
+			Self::Unsupported(part) => write!(f, "unsupported recurrence rule part {:?}", part),
+			Self::InvalidValue { name, value } => write!(f, "invalid value {:?} for {}", value, name),
+			Self::MissingFrequency => write!(f, "recurrence rule has no FREQ"),
+			Self::MissingStart => write!(f, "recurrence rule needs a DTSTART"),
+			Self::CountAndUntil => write!(f, "recurrence rule has both COUNT and UNTIL"),
+		}
+	}
+}
+
+impl std::error::Error for SpecifierError {}
+
+pub type Result<T> = core::result::Result<T, SpecifierError>;
@@ -0,0 +1,554 @@
+use super::error::{self, SpecifierError};
+use super::first_of_next_month;
+use chrono::{prelude::*, Duration, NaiveDate, NaiveDateTime, TimeZone};
+
+/// How many years past the basis to look for an occurrence before deciding
+/// that the rule will never produce another one
+const SEARCH_LIMIT_YEARS: i32 = 9;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Frequency {
+	Daily,
+	Weekly,
+	Monthly,
+	Yearly,
+}
+
+/// A point in time written in a recurrence rule, either floating (i.e. in the
+/// local time of whatever the rule is evaluated in) or in UTC
+#[derive(Clone, Copy, Debug, PartialEq)]
+struct RuleTime {
+	time: NaiveDateTime,
+	utc: bool,
+}
+
+impl RuleTime {
+	/// Parse a `DATE-TIME` value, or a `DATE` value which is taken to be at the
+	/// given time of day
+	fn parse(value: &str, time_of_day: NaiveTime) -> Option<Self> {
+		let (value, utc) = match value.strip_suffix('Z') {
+			Some(value) => (value, true),
+			None => (value, false),
+		};
+
+		NaiveDateTime::parse_from_str(value, "%Y%m%dT%H%M%S")
+			.or_else(|_| {
+				NaiveDate::parse_from_str(value, "%Y%m%d").map(|date| date.and_time(time_of_day))
+			})
+			.ok()
+			.map(|time| RuleTime { time, utc })
+	}
+
+	fn local<Tz: TimeZone>(&self, tz: &Tz) -> NaiveDateTime {
+		if self.utc {
+			tz.from_utc_datetime(&self.time).naive_local()
+		} else {
+			self.time
+		}
+	}
+}
+
+/// A recurrence rule, as described by the `RRULE` property of RFC 5545
+///
+/// Only the `FREQ` (`DAILY` through `YEARLY`), `INTERVAL`, `BYDAY`,
+/// `BYMONTHDAY`, `BYMONTH`, `BYHOUR`, `BYMINUTE`, `COUNT` and `UNTIL` parts
+/// are supported. A `DTSTART` may be given on a line before the rule; it is
+/// required whenever the rule can't be computed without it, such as with an
+/// `INTERVAL` or a `COUNT`. Floating times are taken to be local to the time
+/// zone the rule is evaluated in, and a `DATE`-only `UNTIL` includes the whole
+/// of that day.
+#[derive(Clone, Debug, PartialEq)]
+pub struct RRule {
+	frequency: Frequency,
+	interval: u32,
+	by_day: Vec<(Option<i32>, Weekday)>,
+	by_month_day: Vec<i32>,
+	by_month: Vec<u32>,
+	by_hour: Vec<u32>,
+	by_minute: Vec<u32>,
+	count: Option<u32>,
+	until: Option<RuleTime>,
+	start: Option<RuleTime>,
+}
+
+fn parse_weekday(code: &str) -> Option<Weekday> {
+	match code {
+		"MO" => Some(Weekday::Mon),
+		"TU" => Some(Weekday::Tue),
+		"WE" => Some(Weekday::Wed),
+		"TH" => Some(Weekday::Thu),
+		"FR" => Some(Weekday::Fri),
+		"SA" => Some(Weekday::Sat),
+		"SU" => Some(Weekday::Sun),
+		_ => None,
+	}
+}
+
+/// Parse a `BYDAY` entry such as `MO`, `1MO` or `-1FR`
+fn parse_by_day(value: &str) -> Option<(Option<i32>, Weekday)> {
+	if value.len() < 2 || !value.is_char_boundary(value.len() - 2) {
+		return None;
+	}
+
+	let (ordinal, code) = value.split_at(value.len() - 2);
+	let weekday = parse_weekday(code)?;
+
+	match ordinal {
+		"" => Some((None, weekday)),
+		ordinal => match ordinal.parse::<i32>() {
+			Ok(n) if n != 0 && n.abs() <= 53 => Some((Some(n), weekday)),
+			_ => None,
+		},
+	}
+}
+
+/// Parse a comma-separated list of values, each with the given function
+fn parse_list<T, F>(name: &str, value: &str, mut parse: F) -> error::Result<Vec<T>>
+where
+	F: FnMut(&str) -> Option<T>,
+{
+	value
+		.split(',')
+		.map(|item| {
+			parse(item).ok_or_else(|| SpecifierError::InvalidValue {
+				name: name.to_string(),
+				value: value.to_string(),
+			})
+		})
+		.collect()
+}
+
+fn parse_in_range<T>(item: &str, range: core::ops::RangeInclusive<T>) -> Option<T>
+where
+	T: core::str::FromStr + PartialOrd,
+{
+	item.parse().ok().filter(|n| range.contains(n))
+}
+
+fn days_in_month(year: i32, month: u32) -> u32 {
+	let first = NaiveDate::from_ymd(year, month, 1);
+	first_of_next_month(first).pred().day()
+}
+
+impl core::str::FromStr for RRule {
+	type Err = SpecifierError;
+
+	fn from_str(rule: &str) -> error::Result<Self> {
+		let mut frequency: Option<Frequency> = None;
+		let mut by_day = "";
+		let mut parsed = RRule {
+			frequency: Frequency::Daily,
+			interval: 1,
+			by_day: Vec::new(),
+			by_month_day: Vec::new(),
+			by_month: Vec::new(),
+			by_hour: Vec::new(),
+			by_minute: Vec::new(),
+			count: None,
+			until: None,
+			start: None,
+		};
+
+		let invalid = |name: &str, value: &str| SpecifierError::InvalidValue {
+			name: name.to_string(),
+			value: value.to_string(),
+		};
+
+		for line in rule.split_whitespace() {
+			if line.starts_with("DTSTART") {
+				// Any parameters, such as TZID, are ignored; the value follows the last
+				// colon.
+				let value = line.rsplit(':').next().unwrap_or_default();
+				parsed.start = Some(
+					RuleTime::parse(value, NaiveTime::from_hms(0, 0, 0))
+						.ok_or_else(|| invalid("DTSTART", value))?,
+				);
+				continue;
+			}
+
+			let line = line.strip_prefix("RRULE:").unwrap_or(line);
+
+			for part in line.split(';').filter(|part| !part.is_empty()) {
+				let mut pieces = part.splitn(2, '=');
+				let name = pieces.next().unwrap_or_default();
+				let value = pieces.next().ok_or_else(|| invalid(name, ""))?;
+
+				match name {
+					"FREQ" => {
+						frequency = Some(match value {
+							"DAILY" => Frequency::Daily,
+							"WEEKLY" => Frequency::Weekly,
+							"MONTHLY" => Frequency::Monthly,
+							"YEARLY" => Frequency::Yearly,
+							"SECONDLY" | "MINUTELY" | "HOURLY" => {
+								return Err(SpecifierError::Unsupported(part.to_string()))
+							}
+							_ => return Err(invalid(name, value)),
+						})
+					}
+					"INTERVAL" => {
+						parsed.interval =
+							parse_in_range(value, 1..=u32::MAX).ok_or_else(|| invalid(name, value))?
+					}
+					"COUNT" => {
+						parsed.count =
+							Some(parse_in_range(value, 1..=u32::MAX).ok_or_else(|| invalid(name, value))?)
+					}
+					"UNTIL" => {
+						parsed.until = Some(
+							RuleTime::parse(value, NaiveTime::from_hms(23, 59, 59))
+								.ok_or_else(|| invalid(name, value))?,
+						)
+					}
+					"BYDAY" => {
+						by_day = value;
+						parsed.by_day = parse_list(name, value, parse_by_day)?
+					}
+					"BYMONTHDAY" => {
+						parsed.by_month_day = parse_list(name, value, |item| {
+							parse_in_range(item, -31..=31).filter(|n| *n != 0)
+						})?
+					}
+					"BYMONTH" => {
+						parsed.by_month = parse_list(name, value, |item| parse_in_range(item, 1..=12))?
+					}
+					"BYHOUR" => {
+						parsed.by_hour = parse_list(name, value, |item| parse_in_range(item, 0..=23))?
+					}
+					"BYMINUTE" => {
+						parsed.by_minute = parse_list(name, value, |item| parse_in_range(item, 0..=59))?
+					}
+					_ => return Err(SpecifierError::Unsupported(part.to_string())),
+				}
+			}
+		}
+
+		parsed.frequency = frequency.ok_or(SpecifierError::MissingFrequency)?;
+
+		if parsed.count.is_some() && parsed.until.is_some() {
+			return Err(SpecifierError::CountAndUntil);
+		}
+
+		let has_ordinals = parsed.by_day.iter().any(|(ordinal, _)| ordinal.is_some());
+		if has_ordinals && matches!(parsed.frequency, Frequency::Daily | Frequency::Weekly) {
+			return Err(invalid("BYDAY", by_day));
+		}
+
+		if parsed.start.is_none() {
+			let needs_start = parsed.interval > 1
+				|| parsed.count.is_some()
+				|| match parsed.frequency {
+					Frequency::Daily => false,
+					Frequency::Weekly => parsed.by_day.is_empty(),
+					Frequency::Monthly | Frequency::Yearly => {
+						parsed.by_day.is_empty() && parsed.by_month_day.is_empty()
+					}
+				};
+
+			if needs_start {
+				return Err(SpecifierError::MissingStart);
+			}
+		}
+
+		Ok(parsed)
+	}
+}
+
+impl RRule {
+	/// Find the first occurrence after `from` (or at it, if `inclusive`), in the
+	/// local time of `tz`
+	pub(crate) fn next_occurrence<Tz: TimeZone>(
+		&self,
+		tz: &Tz,
+		from: NaiveDateTime,
+		inclusive: bool,
+	) -> Option<NaiveDateTime> {
+		let start = self.start.map(|start| start.local(tz));
+		let until = self.until.map(|until| until.local(tz));
+
+		// With a COUNT every occurrence since the start must be counted, but
+		// otherwise we can skip straight to the period the search starts in.
+		let mut period = match (start, self.count) {
+			(Some(start), Some(_)) => self.period_of(start.date()),
+			(Some(start), None) => {
+				let first = self.period_of(start.date());
+				let periods = self
+					.periods_between(first, self.period_of(from.date()))
+					.max(0);
+				self.advance(first, periods - periods % i64::from(self.interval))
+			}
+			(None, _) => self.period_of(from.date()),
+		};
+
+		let limit = from.year().max(period.year()) + SEARCH_LIMIT_YEARS;
+		let mut seen: u32 = 0;
+
+		while period.year() <= limit {
+			for occurrence in self.expand(period, start) {
+				if start.is_some_and(|start| occurrence < start) {
+					continue;
+				}
+
+				if until.is_some_and(|until| occurrence > until) {
+					return None;
+				}
+
+				seen += 1;
+				if self.count.is_some_and(|count| seen > count) {
+					return None;
+				}
+
+				if occurrence > from || (inclusive && occurrence == from) {
+					return Some(occurrence);
+				}
+			}
+
+			period = self.advance(period, self.interval.into());
+		}
+
+		None
+	}
+
+	/// Compute the first day of the period (day, week, month or year) that the
+	/// given date falls in
+	fn period_of(&self, date: NaiveDate) -> NaiveDate {
+		match self.frequency {
+			Frequency::Daily => date,
+			Frequency::Weekly => date - Duration::days(date.weekday().num_days_from_monday().into()),
+			Frequency::Monthly => date.with_day(1).unwrap(),
+			Frequency::Yearly => NaiveDate::from_ymd(date.year(), 1, 1),
+		}
+	}
+
+	fn periods_between(&self, from: NaiveDate, to: NaiveDate) -> i64 {
+		match self.frequency {
+			Frequency::Daily => (to - from).num_days(),
+			Frequency::Weekly => (to - from).num_weeks(),
+			Frequency::Monthly => {
+				i64::from(to.year() - from.year()) * 12 + i64::from(to.month()) - i64::from(from.month())
+			}
+			Frequency::Yearly => i64::from(to.year() - from.year()),
+		}
+	}
+
+	fn advance(&self, period: NaiveDate, periods: i64) -> NaiveDate {
+		match self.frequency {
+			Frequency::Daily => period + Duration::days(periods),
+			Frequency::Weekly => period + Duration::weeks(periods),
+			Frequency::Monthly => {
+				let months = i64::from(period.year()) * 12 + i64::from(period.month0()) + periods;
+				NaiveDate::from_ymd((months / 12) as i32, (months % 12) as u32 + 1, 1)
+			}
+			Frequency::Yearly => NaiveDate::from_ymd(period.year() + periods as i32, 1, 1),
+		}
+	}
+
+	/// Compute every occurrence within the period starting on `period`, in
+	/// order
+	fn expand(&self, period: NaiveDate, start: Option<NaiveDateTime>) -> Vec<NaiveDateTime> {
+		let mut dates: Vec<NaiveDate> = match self.frequency {
+			Frequency::Daily => vec![period]
+				.into_iter()
+				.filter(|date| self.by_month_day.is_empty() || self.matches_month_day(date))
+				.filter(|date| {
+					self.by_day.is_empty() || self.by_day.iter().any(|(_, day)| *day == date.weekday())
+				})
+				.collect(),
+			Frequency::Weekly => (0..7)
+				.map(|offset| period + Duration::days(offset))
+				.filter(|date| match (self.by_day.is_empty(), start) {
+					(false, _) => self.by_day.iter().any(|(_, day)| *day == date.weekday()),
+					(true, Some(start)) => start.weekday() == date.weekday(),
+					(true, None) => false,
+				})
+				.collect(),
+			Frequency::Monthly => self.dates_in_month(period.year(), period.month(), start),
+			Frequency::Yearly => self.dates_in_year(period.year(), start),
+		};
+
+		dates.retain(|date| self.by_month.is_empty() || self.by_month.contains(&date.month()));
+		dates.sort();
+		dates.dedup();
+
+		let hours = match (self.by_hour.is_empty(), start) {
+			(false, _) => self.by_hour.clone(),
+			(true, Some(start)) => vec![start.hour()],
+			(true, None) => vec![0],
+		};
+		let minutes = match (self.by_minute.is_empty(), start) {
+			(false, _) => self.by_minute.clone(),
+			(true, Some(start)) => vec![start.minute()],
+			(true, None) => vec![0],
+		};
+		let second = start.map_or(0, |start| start.second());
+
+		let mut times: Vec<NaiveTime> = hours
+			.iter()
+			.flat_map(|hour| {
+				minutes
+					.iter()
+					.map(move |minute| NaiveTime::from_hms(*hour, *minute, second))
+			})
+			.collect();
+		times.sort();
+		times.dedup();
+
+		dates
+			.iter()
+			.flat_map(|date| times.iter().map(move |time| date.and_time(*time)))
+			.collect()
+	}
+
+	fn matches_month_day(&self, date: &NaiveDate) -> bool {
+		let length = days_in_month(date.year(), date.month()) as i32;
+
+		self.by_month_day.iter().any(|day| match *day {
+			day if day > 0 => day == date.day() as i32,
+			day => length + day + 1 == date.day() as i32,
+		})
+	}
+
+	/// Select the dates within `scope` (a month or year) which match `BYDAY`,
+	/// taking ordinals to be relative to the scope
+	fn select_weekdays(&self, scope: &[NaiveDate]) -> Vec<NaiveDate> {
+		self
+			.by_day
+			.iter()
+			.flat_map(|(ordinal, weekday)| {
+				let matching: Vec<NaiveDate> = scope
+					.iter()
+					.cloned()
+					.filter(|date| date.weekday() == *weekday)
+					.collect();
+
+				match *ordinal {
+					None => matching,
+					Some(n) if n > 0 => matching.get(n as usize - 1).cloned().into_iter().collect(),
+					Some(n) => matching
+						.len()
+						.checked_sub(n.unsigned_abs() as usize)
+						.and_then(|index| matching.get(index).cloned())
+						.into_iter()
+						.collect(),
+				}
+			})
+			.collect()
+	}
+
+	fn dates_in_month(&self, year: i32, month: u32, start: Option<NaiveDateTime>) -> Vec<NaiveDate> {
+		let days: Vec<NaiveDate> = (1..=days_in_month(year, month))
+			.map(|day| NaiveDate::from_ymd(year, month, day))
+			.collect();
+
+		match (self.by_month_day.is_empty(), self.by_day.is_empty()) {
+			(false, true) => days
+				.into_iter()
+				.filter(|date| self.matches_month_day(date))
+				.collect(),
+			(true, false) => self.select_weekdays(&days),
+			(false, false) => {
+				let weekdays = self.select_weekdays(&days);
+				days
+					.into_iter()
+					.filter(|date| self.matches_month_day(date) && weekdays.contains(date))
+					.collect()
+			}
+			(true, true) => start
+				.and_then(|start| NaiveDate::from_ymd_opt(year, month, start.day()))
+				.into_iter()
+				.collect(),
+		}
+	}
+
+	fn dates_in_year(&self, year: i32, start: Option<NaiveDateTime>) -> Vec<NaiveDate> {
+		if !self.by_month.is_empty() {
+			self
+				.by_month
+				.iter()
+				.flat_map(|month| self.dates_in_month(year, *month, start))
+				.collect()
+		} else if !self.by_month_day.is_empty() {
+			(1..=12)
+				.flat_map(|month| self.dates_in_month(year, month, start))
+				.collect()
+		} else if !self.by_day.is_empty() {
+			let first = NaiveDate::from_ymd(year, 1, 1);
+			let days: Vec<NaiveDate> = (0..)
+				.map(|offset| first + Duration::days(offset))
+				.take_while(|date| date.year() == year)
+				.collect();
+
+			self.select_weekdays(&days)
+		} else {
+			start
+				.and_then(|start| NaiveDate::from_ymd_opt(year, start.month(), start.day()))
+				.into_iter()
+				.collect()
+		}
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	mod rrule {
+		use crate::{RRule, SpecifierError};
+
+		#[test]
+		fn parse_requires_frequency() {
+			assert_eq!(
+				"BYDAY=MO".parse::<RRule>(),
+				Err(SpecifierError::MissingFrequency)
+			);
+		}
+
+		#[test]
+		fn parse_rejects_unsupported_parts() {
+			assert_eq!(
+				"FREQ=HOURLY".parse::<RRule>(),
+				Err(SpecifierError::Unsupported("FREQ=HOURLY".to_string()))
+			);
+			assert_eq!(
+				"FREQ=MONTHLY;BYDAY=MO;BYSETPOS=1".parse::<RRule>(),
+				Err(SpecifierError::Unsupported("BYSETPOS=1".to_string()))
+			);
+		}
+
+		#[test]
+		fn parse_rejects_invalid_values() {
+			assert_eq!(
+				"FREQ=WEEKLY;BYDAY=XX".parse::<RRule>(),
+				Err(SpecifierError::InvalidValue {
+					name: "BYDAY".to_string(),
+					value: "XX".to_string()
+				})
+			);
+			assert_eq!(
+				"FREQ=DAILY;BYHOUR=24".parse::<RRule>(),
+				Err(SpecifierError::InvalidValue {
+					name: "BYHOUR".to_string(),
+					value: "24".to_string()
+				})
+			);
+		}
+
+		#[test]
+		fn parse_requires_start_for_interval_and_count() {
+			assert_eq!(
+				"FREQ=WEEKLY;INTERVAL=2;BYDAY=MO".parse::<RRule>(),
+				Err(SpecifierError::MissingStart)
+			);
+			assert_eq!(
+				"FREQ=DAILY;COUNT=3".parse::<RRule>(),
+				Err(SpecifierError::MissingStart)
+			);
+		}
+
+		#[test]
+		fn parse_rejects_count_and_until() {
+			assert_eq!(
+				"DTSTART:20200101T070000\nRRULE:FREQ=DAILY;COUNT=3;UNTIL=20200201".parse::<RRule>(),
+				Err(SpecifierError::CountAndUntil)
+			);
+		}
+	}
+}
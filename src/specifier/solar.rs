@@ -0,0 +1,76 @@
+use chrono::{Duration, NaiveDate, NaiveDateTime};
+
+/// The obliquity of the ecliptic, in degrees
+const OBLIQUITY: f64 = 23.4397;
+
+/// A point in the daily path of the sun
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum SolarEvent {
+	/// The top of the sun appears over the horizon
+	Sunrise,
+	/// The top of the sun disappears below the horizon
+	Sunset,
+	/// Morning civil twilight begins, with the sun 6° below the horizon
+	CivilDawn,
+	/// Evening civil twilight ends, with the sun 6° below the horizon
+	CivilDusk,
+}
+
+impl SolarEvent {
+	/// The altitude of the centre of the sun at this event, in degrees,
+	/// accounting for atmospheric refraction and the size of the sun
+	fn altitude(self) -> f64 {
+		match self {
+			SolarEvent::Sunrise | SolarEvent::Sunset => -0.833,
+			SolarEvent::CivilDawn | SolarEvent::CivilDusk => -6.0,
+		}
+	}
+
+	fn is_morning(self) -> bool {
+		matches!(self, SolarEvent::Sunrise | SolarEvent::CivilDawn)
+	}
+
+	/// Compute the UTC time of this event on the given date, at a place given
+	/// in degrees north and east
+	///
+	/// This uses the sunrise equation, which is accurate to within a minute or
+	/// two. It is `None` if the sun doesn't cross the event's altitude that day,
+	/// such as during the midnight sun or polar night.
+	pub(crate) fn on(self, date: NaiveDate, latitude: f64, longitude: f64) -> Option<NaiveDateTime> {
+		let epoch = NaiveDate::from_ymd(2000, 1, 1).and_hms(12, 0, 0);
+
+		// Days since the J2000 epoch, at the mean solar noon of the place
+		let n = (date - epoch.date()).num_days() as f64 + 0.0008;
+		let mean_noon = n - longitude / 360.0;
+
+		let anomaly = (357.5291 + 0.985_600_28 * mean_noon)
+			.rem_euclid(360.0)
+			.to_radians();
+		let center =
+			1.9148 * anomaly.sin() + 0.0200 * (2.0 * anomaly).sin() + 0.0003 * (3.0 * anomaly).sin();
+		let ecliptic_longitude = (anomaly.to_degrees() + center + 180.0 + 102.9372)
+			.rem_euclid(360.0)
+			.to_radians();
+
+		let transit = mean_noon + 0.0053 * anomaly.sin() - 0.0069 * (2.0 * ecliptic_longitude).sin();
+
+		let declination = (ecliptic_longitude.sin() * OBLIQUITY.to_radians().sin()).asin();
+		let latitude = latitude.to_radians();
+
+		let cos_hour_angle = (self.altitude().to_radians().sin() - latitude.sin() * declination.sin())
+			/ (latitude.cos() * declination.cos());
+
+		if !(-1.0..=1.0).contains(&cos_hour_angle) {
+			return None;
+		}
+
+		let hour_angle = cos_hour_angle.acos().to_degrees() / 360.0;
+		let event = if self.is_morning() {
+			transit - hour_angle
+		} else {
+			transit + hour_angle
+		};
+
+		Some(epoch + Duration::milliseconds((event * 86_400_000.0).round() as i64))
+	}
+}
@@ -0,0 +1,120 @@
+use super::error::{self, SpecifierError};
+use chrono::Weekday;
+
+/// A set of days of the week, such as Monday through Friday
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct WeekdaySet(u8);
+
+const WEEK: [Weekday; 7] = [
+	Weekday::Mon,
+	Weekday::Tue,
+	Weekday::Wed,
+	Weekday::Thu,
+	Weekday::Fri,
+	Weekday::Sat,
+	Weekday::Sun,
+];
+
+impl WeekdaySet {
+	pub fn new() -> Self {
+		Self::default()
+	}
+
+	/// Every day from `first` through `last`, wrapping around the end of the
+	/// week if `last` comes before `first`
+	pub fn range(first: Weekday, last: Weekday) -> Self {
+		let mut set = Self::new();
+		let mut day = first;
+
+		loop {
+			set.insert(day);
+			if day == last {
+				return set;
+			}
+			day = day.succ();
+		}
+	}
+
+	/// Monday through Friday
+	pub fn weekdays() -> Self {
+		Self::range(Weekday::Mon, Weekday::Fri)
+	}
+
+	/// Saturday and Sunday
+	pub fn weekends() -> Self {
+		Self::range(Weekday::Sat, Weekday::Sun)
+	}
+
+	pub fn insert(&mut self, day: Weekday) {
+		self.0 |= 1 << day.num_days_from_monday();
+	}
+
+	pub fn contains(&self, day: Weekday) -> bool {
+		self.0 & (1 << day.num_days_from_monday()) != 0
+	}
+
+	pub fn is_empty(&self) -> bool {
+		self.0 == 0
+	}
+
+	/// The set of days which follow the days in this set, e.g. Tuesday through
+	/// Saturday for Monday through Friday
+	pub fn succ(&self) -> Self {
+		self.iter().map(|day| day.succ()).collect()
+	}
+
+	/// Iterate over the days in the set, starting from Monday
+	pub fn iter(&self) -> impl Iterator<Item = Weekday> + '_ {
+		WEEK.iter().cloned().filter(move |day| self.contains(*day))
+	}
+}
+
+impl core::iter::FromIterator<Weekday> for WeekdaySet {
+	fn from_iter<I: IntoIterator<Item = Weekday>>(days: I) -> Self {
+		let mut set = Self::new();
+		for day in days {
+			set.insert(day);
+		}
+		set
+	}
+}
+
+impl core::str::FromStr for WeekdaySet {
+	type Err = SpecifierError;
+
+	/// Parse a comma-separated list of days and ranges of days, such as
+	/// `Mon-Fri`, `Sat,Sun` or `Mon,Wed-Fri`; `weekdays` and `weekends` are also
+	/// accepted
+	fn from_str(s: &str) -> error::Result<Self> {
+		let parse_day = |day: &str| -> error::Result<Weekday> {
+			day
+				.trim()
+				.parse()
+				.map_err(|_| SpecifierError::InvalidDay(s.to_string()))
+		};
+
+		let mut set = Self::new();
+
+		for item in s.split(',') {
+			let item = item.trim();
+
+			let days = if item.eq_ignore_ascii_case("weekdays") {
+				Self::weekdays()
+			} else if item.eq_ignore_ascii_case("weekends") {
+				Self::weekends()
+			} else {
+				let mut ends = item.splitn(2, '-');
+				let first = parse_day(ends.next().unwrap_or_default())?;
+
+				match ends.next() {
+					Some(last) => Self::range(first, parse_day(last)?),
+					None => core::iter::once(first).collect(),
+				}
+			};
+
+			set.0 |= days.0;
+		}
+
+		Ok(set)
+	}
+}
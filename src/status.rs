@@ -1,19 +1,42 @@
 use chrono::{DateTime, TimeZone};
+use std::sync::Arc;
+
+mod error;
+
+pub use error::{Rule, RuleError, StatusError};
 
 #[derive(Clone, Debug, PartialEq)]
-pub enum Reason<'schedule, Tz: TimeZone> {
+pub enum Reason<Tz: TimeZone> {
 	Exception(Option<String>),
-	Part(Option<&'schedule super::Part<Tz>>),
+	Part(Option<Arc<super::Part<Tz>>>),
+	/// The space is open for `part`, but the parts in `closed` would also apply
+	/// if not for exceptions which target them
+	Reduced {
+		part: Arc<super::Part<Tz>>,
+		closed: Vec<Arc<super::Part<Tz>>>,
+	},
 }
 
 #[derive(Clone, Debug, PartialEq)]
-pub enum Status<'schedule, Tz: TimeZone> {
-	Open(Reason<'schedule, Tz>),
-	Closed(Reason<'schedule, Tz>),
+pub enum Status<Tz: TimeZone> {
+	Open(Reason<Tz>),
+	Closed(Reason<Tz>),
+}
+
+impl<Tz: TimeZone> Status<Tz> {
+	pub fn is_open(&self) -> bool {
+		matches!(self, Status::Open(_))
+	}
+
+	/// Determine whether the space is open with some of its parts closed by
+	/// exceptions which target them
+	pub fn is_reduced(&self) -> bool {
+		matches!(self, Status::Open(Reason::Reduced { .. }))
+	}
 }
 
 #[derive(Clone, Debug, PartialEq)]
-pub enum StatusChange<'schedule, Tz: TimeZone> {
-	Opening(DateTime<Tz>, Reason<'schedule, Tz>),
-	Closing(DateTime<Tz>, Reason<'schedule, Tz>),
+pub enum StatusChange<Tz: TimeZone> {
+	Opening(DateTime<Tz>, Reason<Tz>),
+	Closing(DateTime<Tz>, Reason<Tz>),
 }
@@ -0,0 +1,61 @@
+/// A problem with a single part or exception, found while evaluating it
+#[derive(Clone, Debug, PartialEq)]
+pub enum RuleError {
+	/// The named specifier (`open`, `close`, `effective` or `expires`) has no
+	/// instance where one is needed, e.g. a part which opens but never closes
+	NoInstance(&'static str),
+}
+
+impl core::fmt::Display for RuleError {
+	fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+		match self {
+			Self::NoInstance(specifier) => write!(f, "{} specifier has no instance", specifier),
+		}
+	}
+}
+
+impl std::error::Error for RuleError {}
+
+/// A part or exception of a [`Schedule`](crate::Schedule), by its position in
+/// the schedule
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Rule {
+	Part(usize),
+	Exception(usize),
+}
+
+impl core::fmt::Display for Rule {
+	fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+		match self {
+			Self::Part(index) => write!(f, "part {}", index),
+			Self::Exception(index) => write!(f, "exception {}", index),
+		}
+	}
+}
+
+/// An error computing the status of a [`Space`](crate::Space), naming the
+/// space, schedule and part or exception at fault
+#[derive(Clone, Debug, PartialEq)]
+pub struct StatusError {
+	pub space: String,
+	/// The position of the schedule in the space
+	pub schedule: usize,
+	pub rule: Rule,
+	pub error: RuleError,
+}
+
+impl core::fmt::Display for StatusError {
+	fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+		write!(
+			f,
+			"space {:?}, schedule {}, {}: {}",
+			self.space, self.schedule, self.rule, self.error
+		)
+	}
+}
+
+impl std::error::Error for StatusError {
+	fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+		Some(&self.error)
+	}
+}
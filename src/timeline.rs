@@ -0,0 +1,82 @@
+use super::{Space, Status, StatusError};
+use chrono::{DateTime, TimeZone};
+
+/// A stretch of time from `start` (inclusive) until `end` (exclusive) during
+/// which a space has the given status, as `(start, end, status)`
+pub type Stretch<Tz> = (DateTime<Tz>, DateTime<Tz>, Status<Tz>);
+
+/// An iterator over the contiguous stretches of time during which the status
+/// of a [`Space`] stays the same
+///
+/// Each item is `(start, end, status)`, where `start` is inclusive and `end`
+/// is exclusive. The first item starts at the beginning of the range and the
+/// last one ends at the end of it, with each item starting where the previous
+/// one ended. If the status can't be computed, the error is yielded in place
+/// of an item and iteration stops.
+#[derive(Debug)]
+pub struct Timeline<'space, Tz: TimeZone> {
+	space: &'space Space<Tz>,
+	cursor: DateTime<Tz>,
+	to: DateTime<Tz>,
+}
+
+impl<'space, Tz: TimeZone> Timeline<'space, Tz> {
+	pub(crate) fn new(space: &'space Space<Tz>, from: &DateTime<Tz>, to: &DateTime<Tz>) -> Self {
+		Self {
+			space,
+			cursor: from.to_owned(),
+			to: to.to_owned(),
+		}
+	}
+}
+
+impl<'space, Tz: TimeZone> Timeline<'space, Tz>
+where
+	Status<Tz>: PartialEq,
+{
+	/// Compute the stretch of time starting at the cursor
+	fn stretch(&self) -> Result<Stretch<Tz>, StatusError> {
+		let start = self.cursor.to_owned();
+		let status = self.space.status_at(&start)?;
+
+		// Skip over transitions which don't actually change the status, such as a
+		// part closing at the same moment another one opens.
+		let mut basis = start.to_owned();
+		let end = loop {
+			match self.space.next_transition_after(&basis) {
+				Some(candidate) if candidate < self.to => {
+					if self.space.status_at(&candidate)? != status {
+						break candidate;
+					}
+					basis = candidate;
+				}
+				_ => break self.to.to_owned(),
+			}
+		};
+
+		Ok((start, end, status))
+	}
+}
+
+impl<'space, Tz: TimeZone> Iterator for Timeline<'space, Tz>
+where
+	Status<Tz>: PartialEq,
+{
+	type Item = Result<Stretch<Tz>, StatusError>;
+
+	fn next(&mut self) -> Option<Self::Item> {
+		if self.cursor >= self.to {
+			return None;
+		}
+
+		let item = self.stretch();
+
+		self.cursor = match &item {
+			Ok((_, end, _)) => end.to_owned(),
+			// Stop after an error, rather than yielding it over and over.
+			Err(_) => self.to.to_owned(),
+		};
+
+		Some(item)
+	}
+}
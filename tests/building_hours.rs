@@ -1,5 +1,9 @@
-use chrono::{DateTime, FixedOffset};
-use sked::{Exception, Part, Reason, Schedule, Space, Specifier, Status};
+use chrono::{DateTime, Duration, FixedOffset, NaiveTime, Weekday};
+use sked::{Exception, Part, Reason, Schedule, Space, Specifier, Status, StatusChange};
+use std::sync::Arc;
+
+mod common;
+use common::{assert_fast_paths_match, at};
 
 #[cfg(test)]
 mod tests {
@@ -11,7 +15,7 @@ mod tests {
 			fn $test_name() {
 				let (space, $var): (Space<FixedOffset>, Part<FixedOffset>) = generate_space("asdf");
 				let time: DateTime<FixedOffset> = DateTime::parse_from_rfc3339($time).unwrap();
-				assert_eq!(space.status_at(&time), $expected);
+				assert_eq!(space.status_at(&time), Ok($expected));
 			}
 		};
 		($test_name:ident, $time:literal, $expected:expr) => {
@@ -19,15 +23,22 @@ mod tests {
 		};
 	}
 
+	fn thursday(hour: u32, minute: u32) -> Specifier<FixedOffset> {
+		Specifier::Weekly {
+			day: Weekday::Thu,
+			time: NaiveTime::from_hms(hour, minute, 0),
+		}
+	}
+
 	fn generate_space(name: &str) -> (Space<FixedOffset>, Part<FixedOffset>) {
 		let mut exception = Exception::new()
 			.effective(Specifier::Weekly {
-				day: "Thursday".to_string(),
-				time: "10:15".to_string(),
+				day: Weekday::Thu,
+				time: NaiveTime::from_hms(10, 15, 0),
 			})
 			.expires(Specifier::Weekly {
-				day: "Thursday".to_string(),
-				time: "11:00".to_string(),
+				day: Weekday::Thu,
+				time: NaiveTime::from_hms(11, 0, 0),
 			});
 
 		*exception.effect_mut() = Some(Status::Closed(Reason::Exception(Some(
@@ -36,12 +47,12 @@ mod tests {
 
 		let part = Part::new()
 			.open(Specifier::Weekly {
-				day: "Thursday".to_string(),
-				time: "07:00".to_string(),
+				day: Weekday::Thu,
+				time: NaiveTime::from_hms(7, 0, 0),
 			})
 			.close(Specifier::Weekly {
-				day: "Thursday".to_string(),
-				time: "17:00".to_string(),
+				day: Weekday::Thu,
+				time: NaiveTime::from_hms(17, 0, 0),
 			});
 
 		let mut schedule: Schedule<FixedOffset> =
@@ -72,7 +83,7 @@ mod tests {
 			is_open,
 			"2020-01-16T07:00:00-06:00",
 			__main_part__,
-			Status::Open(Reason::Part(Some(&__main_part__)))
+			Status::Open(Reason::Part(Some(Arc::new(__main_part__.clone()))))
 		);
 	}
 
@@ -83,7 +94,7 @@ mod tests {
 			is_open,
 			"2020-01-16T10:00:00-06:00",
 			__main_part__,
-			Status::Open(Reason::Part(Some(&__main_part__)))
+			Status::Open(Reason::Part(Some(Arc::new(__main_part__.clone()))))
 		);
 	}
 
@@ -94,7 +105,7 @@ mod tests {
 			is_open,
 			"2020-01-16T10:14:59-06:00",
 			__main_part__,
-			Status::Open(Reason::Part(Some(&__main_part__)))
+			Status::Open(Reason::Part(Some(Arc::new(__main_part__.clone()))))
 		);
 	}
 
@@ -135,7 +146,7 @@ mod tests {
 			is_open,
 			"2020-01-16T11:00:00-06:00",
 			__main_part__,
-			Status::Open(Reason::Part(Some(&__main_part__)))
+			Status::Open(Reason::Part(Some(Arc::new(__main_part__.clone()))))
 		);
 	}
 
@@ -146,7 +157,7 @@ mod tests {
 			is_open,
 			"2020-01-16T16:59:59-06:00",
 			__main_part__,
-			Status::Open(Reason::Part(Some(&__main_part__)))
+			Status::Open(Reason::Part(Some(Arc::new(__main_part__.clone()))))
 		);
 	}
 
@@ -169,4 +180,1463 @@ mod tests {
 			Status::Closed(Reason::Part(None))
 		);
 	}
+
+	mod next_status_change {
+		use super::*;
+
+		macro_rules! check_next_change_at_time {
+			($test_name:ident, $time:literal, $var:ident, $expected:expr) => {
+				#[test]
+				fn $test_name() {
+					let (space, $var): (Space<FixedOffset>, Part<FixedOffset>) = generate_space("asdf");
+					let time: DateTime<FixedOffset> = DateTime::parse_from_rfc3339($time).unwrap();
+					assert_eq!(space.next_status_change_at(&time), Ok($expected));
+				}
+			};
+			($test_name:ident, $time:literal, $expected:expr) => {
+				check_next_change_at_time!($test_name, $time, __nil__, $expected);
+			};
+		}
+
+		check_next_change_at_time!(
+			before_open_is_opening,
+			"2020-01-16T06:00:00-06:00",
+			__main_part__,
+			Some(StatusChange::Opening(
+				at("2020-01-16T07:00:00-06:00"),
+				Reason::Part(Some(Arc::new(__main_part__.clone())))
+			))
+		);
+
+		check_next_change_at_time!(
+			while_open_is_exception_closing,
+			"2020-01-16T07:00:00-06:00",
+			Some(StatusChange::Closing(
+				at("2020-01-16T10:15:00-06:00"),
+				Reason::Exception(Some("Closed for lunch.".to_string()))
+			))
+		);
+
+		check_next_change_at_time!(
+			during_exception_is_reopening,
+			"2020-01-16T10:35:00-06:00",
+			__main_part__,
+			Some(StatusChange::Opening(
+				at("2020-01-16T11:00:00-06:00"),
+				Reason::Part(Some(Arc::new(__main_part__.clone())))
+			))
+		);
+
+		check_next_change_at_time!(
+			after_exception_is_closing,
+			"2020-01-16T11:00:00-06:00",
+			Some(StatusChange::Closing(
+				at("2020-01-16T17:00:00-06:00"),
+				Reason::Part(None)
+			))
+		);
+
+		check_next_change_at_time!(
+			after_close_is_opening_next_week,
+			"2020-01-16T18:00:00-06:00",
+			__main_part__,
+			Some(StatusChange::Opening(
+				at("2020-01-23T07:00:00-06:00"),
+				Reason::Part(Some(Arc::new(__main_part__.clone())))
+			))
+		);
+
+		check_next_change_at_time!(after_last_close_is_none, "2020-01-30T18:00:00-06:00", None);
+
+		#[test]
+		fn schedule_expiring_mid_day_is_closing() {
+			let part: Part<FixedOffset> = Part::new()
+				.open(Specifier::Weekly {
+					day: Weekday::Thu,
+					time: NaiveTime::from_hms(7, 0, 0),
+				})
+				.close(Specifier::Weekly {
+					day: Weekday::Thu,
+					time: NaiveTime::from_hms(17, 0, 0),
+				});
+
+			let mut schedule: Schedule<FixedOffset> = Schedule::new().part(part);
+			*schedule.expires_mut() = Some(at("2020-01-16T12:00:00-06:00"));
+
+			let space: Space<FixedOffset> = Space::new("asdf").schedule(schedule);
+
+			assert_eq!(
+				space.next_status_change_at(&at("2020-01-16T08:00:00-06:00")),
+				Ok(Some(StatusChange::Closing(
+					at("2020-01-16T12:00:00-06:00"),
+					Reason::Part(None)
+				)))
+			);
+		}
+
+		#[test]
+		fn schedule_effective_mid_day_is_opening() {
+			let part: Part<FixedOffset> = Part::new().open(thursday(7, 0)).close(thursday(17, 0));
+
+			let mut schedule: Schedule<FixedOffset> = Schedule::new().part(part.clone());
+			*schedule.effective_mut() = Some(at("2020-01-16T12:00:00-06:00"));
+
+			let space: Space<FixedOffset> = Space::new("asdf").schedule(schedule);
+
+			assert_eq!(
+				space.status_at(&at("2020-01-16T12:00:00-06:00")),
+				Ok(Status::Open(Reason::Part(Some(Arc::new(part.clone())))))
+			);
+			assert_eq!(
+				space.next_status_change_at(&at("2020-01-16T08:00:00-06:00")),
+				Ok(Some(StatusChange::Opening(
+					at("2020-01-16T12:00:00-06:00"),
+					Reason::Part(Some(Arc::new(part)))
+				)))
+			);
+		}
+
+		#[test]
+		fn break_schedule_effective_mid_day_is_closing() {
+			let part: Part<FixedOffset> = Part::new().open(thursday(7, 0)).close(thursday(17, 0));
+
+			let mut closed: Schedule<FixedOffset> = Schedule::new();
+			*closed.effective_mut() = Some(at("2020-01-16T12:00:00-06:00"));
+			*closed.expires_mut() = Some(at("2020-01-20T00:00:00-06:00"));
+
+			let space: Space<FixedOffset> = Space::new("asdf")
+				.schedule(Schedule::new().part(part))
+				.schedule(closed);
+
+			assert_eq!(
+				space.next_status_change_at(&at("2020-01-16T08:00:00-06:00")),
+				Ok(Some(StatusChange::Closing(
+					at("2020-01-16T12:00:00-06:00"),
+					Reason::Part(None)
+				)))
+			);
+		}
+	}
+
+	mod timeline {
+		use super::*;
+
+		#[test]
+		fn covers_whole_day() {
+			let (space, part) = generate_space("asdf");
+			let lunch = Status::Closed(Reason::Exception(Some("Closed for lunch.".to_string())));
+
+			assert_eq!(
+				space
+					.timeline(
+						&at("2020-01-16T00:00:00-06:00"),
+						&at("2020-01-17T00:00:00-06:00")
+					)
+					.collect::<Result<Vec<_>, _>>(),
+				Ok(vec![
+					(
+						at("2020-01-16T00:00:00-06:00"),
+						at("2020-01-16T07:00:00-06:00"),
+						Status::Closed(Reason::Part(None))
+					),
+					(
+						at("2020-01-16T07:00:00-06:00"),
+						at("2020-01-16T10:15:00-06:00"),
+						Status::Open(Reason::Part(Some(Arc::new(part.clone()))))
+					),
+					(
+						at("2020-01-16T10:15:00-06:00"),
+						at("2020-01-16T11:00:00-06:00"),
+						lunch
+					),
+					(
+						at("2020-01-16T11:00:00-06:00"),
+						at("2020-01-16T17:00:00-06:00"),
+						Status::Open(Reason::Part(Some(Arc::new(part.clone()))))
+					),
+					(
+						at("2020-01-16T17:00:00-06:00"),
+						at("2020-01-17T00:00:00-06:00"),
+						Status::Closed(Reason::Part(None))
+					),
+				])
+			);
+		}
+
+		#[test]
+		fn crosses_schedule_effective() {
+			let part: Part<FixedOffset> = Part::new().open(thursday(7, 0)).close(thursday(17, 0));
+
+			let mut schedule: Schedule<FixedOffset> = Schedule::new().part(part.clone());
+			*schedule.effective_mut() = Some(at("2020-01-16T12:00:00-06:00"));
+
+			let space: Space<FixedOffset> = Space::new("asdf").schedule(schedule);
+
+			assert_eq!(
+				space
+					.timeline(
+						&at("2020-01-16T00:00:00-06:00"),
+						&at("2020-01-17T00:00:00-06:00")
+					)
+					.collect::<Result<Vec<_>, _>>(),
+				Ok(vec![
+					(
+						at("2020-01-16T00:00:00-06:00"),
+						at("2020-01-16T12:00:00-06:00"),
+						Status::Closed(Reason::Part(None))
+					),
+					(
+						at("2020-01-16T12:00:00-06:00"),
+						at("2020-01-16T17:00:00-06:00"),
+						Status::Open(Reason::Part(Some(Arc::new(part))))
+					),
+					(
+						at("2020-01-16T17:00:00-06:00"),
+						at("2020-01-17T00:00:00-06:00"),
+						Status::Closed(Reason::Part(None))
+					),
+				])
+			);
+		}
+
+		#[test]
+		fn without_transitions_is_single_interval() {
+			let (space, part) = generate_space("asdf");
+
+			assert_eq!(
+				space
+					.timeline(
+						&at("2020-01-16T08:00:00-06:00"),
+						&at("2020-01-16T09:00:00-06:00")
+					)
+					.collect::<Result<Vec<_>, _>>(),
+				Ok(vec![(
+					at("2020-01-16T08:00:00-06:00"),
+					at("2020-01-16T09:00:00-06:00"),
+					Status::Open(Reason::Part(Some(Arc::new(part.clone()))))
+				)])
+			);
+		}
+
+		#[test]
+		fn empty_range_is_empty() {
+			let (space, _) = generate_space("asdf");
+			let time = at("2020-01-16T08:00:00-06:00");
+
+			assert_eq!(space.timeline(&time, &time).count(), 0);
+		}
+	}
+
+	mod named_time_zone {
+		use super::*;
+		use chrono::TimeZone;
+		use chrono_tz::America::Chicago;
+
+		#[test]
+		fn opens_at_local_time_after_spring_forward() {
+			let part = Part::new()
+				.open(Specifier::Daily {
+					time: NaiveTime::from_hms(7, 0, 0),
+				})
+				.close(Specifier::Daily {
+					time: NaiveTime::from_hms(17, 0, 0),
+				});
+			let space = Space::new("asdf").schedule(Schedule::new().part(part.clone()));
+
+			assert_eq!(
+				space.status_at(&Chicago.ymd(2020, 3, 8).and_hms(6, 30, 0)),
+				Ok(Status::Closed(Reason::Part(None)))
+			);
+			assert_eq!(
+				space.status_at(&Chicago.ymd(2020, 3, 8).and_hms(7, 0, 0)),
+				Ok(Status::Open(Reason::Part(Some(Arc::new(part.clone())))))
+			);
+			assert_eq!(
+				space.next_status_change_at(&Chicago.ymd(2020, 3, 7).and_hms(18, 0, 0)),
+				Ok(Some(StatusChange::Opening(
+					Chicago.ymd(2020, 3, 8).and_hms(7, 0, 0),
+					Reason::Part(Some(Arc::new(part.clone())))
+				)))
+			);
+		}
+
+		#[test]
+		fn part_in_spring_forward_gap_stays_closed() {
+			let daily = |hour: u32, minute: u32| Specifier::Daily {
+				time: NaiveTime::from_hms(hour, minute, 0),
+			};
+			let from = Chicago.ymd(2020, 3, 8).and_hms(0, 0, 0);
+			let to = Chicago.ymd(2020, 3, 9).and_hms(0, 0, 0);
+
+			for (open, close) in [(daily(2, 0), daily(3, 0)), (daily(2, 30), daily(3, 0))] {
+				let part = Part::new().open(open).close(close);
+				let space = Space::new("asdf").schedule(Schedule::new().part(part.clone()));
+
+				assert_eq!(
+					space.timeline(&from, &to).collect::<Result<Vec<_>, _>>(),
+					Ok(vec![(from, to, Status::Closed(Reason::Part(None)))])
+				);
+				assert_eq!(
+					space.status_at(&Chicago.ymd(2020, 3, 9).and_hms(2, 45, 0)),
+					Ok(Status::Open(Reason::Part(Some(Arc::new(part)))))
+				);
+			}
+		}
+
+		#[test]
+		fn part_opening_just_after_spring_forward_gap_opens() {
+			let daily = |hour: u32, minute: u32| Specifier::Daily {
+				time: NaiveTime::from_hms(hour, minute, 0),
+			};
+			let parts = vec![
+				Part::new().open(daily(3, 0)).close(daily(2, 0)),
+				Part::new().open(daily(3, 0)).close(daily(2, 30)),
+				Part::new().on_days(
+					sked::WeekdaySet::range(Weekday::Mon, Weekday::Sun),
+					NaiveTime::from_hms(3, 0, 0),
+					NaiveTime::from_hms(3, 0, 0),
+				),
+			];
+			let from = Chicago.ymd(2020, 3, 1).and_hms(0, 0, 0);
+			let to = Chicago.ymd(2020, 3, 15).and_hms(0, 0, 0);
+
+			for part in parts {
+				let space = Space::new("asdf").schedule(Schedule::new().part(part.clone()));
+				let compiled = space.compile(&from, &to).unwrap();
+				let index = space.index(&from, &to).unwrap();
+				let open = Ok(Status::Open(Reason::Part(Some(Arc::new(part)))));
+
+				for time in [
+					Chicago.ymd(2020, 3, 8).and_hms(3, 0, 0),
+					Chicago.ymd(2020, 3, 8).and_hms(12, 0, 0),
+					Chicago.ymd(2020, 3, 9).and_hms(1, 0, 0),
+				] {
+					assert_eq!(space.status_at(&time), open, "at {}", time);
+					assert_eq!(compiled.status_at(&time), open, "compiled at {}", time);
+					assert_eq!(index.status_at(&time), open, "index at {}", time);
+				}
+			}
+		}
+
+		#[test]
+		fn part_moved_out_of_spring_forward_gap_keeps_length() {
+			let part = Part::new()
+				.open(Specifier::Daily {
+					time: NaiveTime::from_hms(2, 0, 0),
+				})
+				.close(Specifier::Daily {
+					time: NaiveTime::from_hms(2, 30, 0),
+				});
+			let space = Space::new("asdf").schedule(Schedule::new().part(part.clone()));
+
+			assert_eq!(
+				space
+					.timeline(
+						&Chicago.ymd(2020, 3, 8).and_hms(0, 0, 0),
+						&Chicago.ymd(2020, 3, 8).and_hms(12, 0, 0)
+					)
+					.collect::<Result<Vec<_>, _>>(),
+				Ok(vec![
+					(
+						Chicago.ymd(2020, 3, 8).and_hms(0, 0, 0),
+						Chicago.ymd(2020, 3, 8).and_hms(3, 0, 0),
+						Status::Closed(Reason::Part(None))
+					),
+					(
+						Chicago.ymd(2020, 3, 8).and_hms(3, 0, 0),
+						Chicago.ymd(2020, 3, 8).and_hms(3, 30, 0),
+						Status::Open(Reason::Part(Some(Arc::new(part))))
+					),
+					(
+						Chicago.ymd(2020, 3, 8).and_hms(3, 30, 0),
+						Chicago.ymd(2020, 3, 8).and_hms(12, 0, 0),
+						Status::Closed(Reason::Part(None))
+					),
+				])
+			);
+		}
+	}
+
+	mod relative_specifier {
+		use super::*;
+
+		#[test]
+		fn last_entry_before_close() {
+			let close = Specifier::Weekly {
+				day: Weekday::Thu,
+				time: NaiveTime::from_hms(17, 0, 0),
+			};
+			let entry = Part::new()
+				.open(Specifier::Weekly {
+					day: Weekday::Thu,
+					time: NaiveTime::from_hms(7, 0, 0),
+				})
+				.close(close.offset_by(chrono::Duration::minutes(-30)));
+			let space: Space<FixedOffset> = Space::new("asdf").schedule(Schedule::new().part(entry));
+
+			assert!(space
+				.status_at(&at("2020-01-16T16:29:59-06:00"))
+				.unwrap()
+				.is_open());
+			assert!(!space
+				.status_at(&at("2020-01-16T16:30:00-06:00"))
+				.unwrap()
+				.is_open());
+		}
+	}
+
+	mod dawn_to_dusk {
+		use super::*;
+		use sked::SolarEvent;
+
+		#[test]
+		fn open_only_during_daylight() {
+			let daylight = |event| Specifier::Solar {
+				event,
+				latitude: 44.4583,
+				longitude: -93.1616,
+			};
+			let quad = Part::new()
+				.open(daylight(SolarEvent::Sunrise))
+				.close(daylight(SolarEvent::Sunset));
+			let space: Space<FixedOffset> = Space::new("quad").schedule(Schedule::new().part(quad));
+
+			assert!(!space
+				.status_at(&at("2020-01-16T06:00:00-06:00"))
+				.unwrap()
+				.is_open());
+			assert!(space
+				.status_at(&at("2020-01-16T12:00:00-06:00"))
+				.unwrap()
+				.is_open());
+			assert!(!space
+				.status_at(&at("2020-01-16T20:00:00-06:00"))
+				.unwrap()
+				.is_open());
+		}
+	}
+
+	mod weekday_set {
+		use super::*;
+		use sked::WeekdaySet;
+
+		#[test]
+		fn open_monday_through_friday() {
+			let part = Part::new().on_days(
+				WeekdaySet::weekdays(),
+				NaiveTime::from_hms(7, 0, 0),
+				NaiveTime::from_hms(17, 0, 0),
+			);
+			let space: Space<FixedOffset> =
+				Space::new("asdf").schedule(Schedule::new().part(part.clone()));
+
+			assert!(space
+				.status_at(&at("2020-01-17T16:00:00-06:00"))
+				.unwrap()
+				.is_open());
+			assert!(!space
+				.status_at(&at("2020-01-18T12:00:00-06:00"))
+				.unwrap()
+				.is_open());
+			assert_eq!(
+				space.next_status_change_at(&at("2020-01-17T18:00:00-06:00")),
+				Ok(Some(StatusChange::Opening(
+					at("2020-01-20T07:00:00-06:00"),
+					Reason::Part(Some(Arc::new(part.clone())))
+				)))
+			);
+		}
+	}
+
+	mod compiled {
+		use super::*;
+
+		#[test]
+		fn matches_status_at_on_boundaries() {
+			let (space, _) = generate_space("asdf");
+			let from = DateTime::parse_from_rfc3339("2020-01-16T07:00:00-06:00").unwrap();
+			let to = DateTime::parse_from_rfc3339("2020-01-16T17:00:00-06:00").unwrap();
+			let compiled = space.compile(&from, &to).unwrap();
+
+			for time in &[
+				"2020-01-16T06:59:59-06:00",
+				"2020-01-16T07:00:00-06:00",
+				"2020-01-16T10:14:59-06:00",
+				"2020-01-16T10:15:00-06:00",
+				"2020-01-16T11:00:00-06:00",
+				"2020-01-16T16:59:59-06:00",
+				"2020-01-16T17:00:00-06:00",
+			] {
+				let time = DateTime::parse_from_rfc3339(time).unwrap();
+				assert_eq!(
+					compiled.status_at(&time),
+					space.status_at(&time),
+					"at {}",
+					time
+				);
+			}
+		}
+	}
+
+	mod index {
+		use super::*;
+
+		#[test]
+		fn iterates_like_timeline() {
+			let (space, _) = generate_space("asdf");
+			let from = at("2020-01-16T00:00:00-06:00");
+			let to = at("2020-01-24T00:00:00-06:00");
+			let index = space.index(&from, &to).unwrap();
+
+			assert_eq!(
+				index
+					.iter()
+					.map(|(start, end, status)| (*start, *end, status.clone()))
+					.collect::<Vec<_>>(),
+				space
+					.timeline(&from, &to)
+					.collect::<Result<Vec<_>, _>>()
+					.unwrap()
+			);
+		}
+
+		#[test]
+		fn change_past_window_falls_back() {
+			let (space, part) = generate_space("asdf");
+			let index = space
+				.index(
+					&at("2020-01-16T18:00:00-06:00"),
+					&at("2020-01-17T00:00:00-06:00"),
+				)
+				.unwrap();
+
+			assert_eq!(
+				index.next_status_change_at(&at("2020-01-16T20:00:00-06:00")),
+				Ok(Some(StatusChange::Opening(
+					at("2020-01-23T07:00:00-06:00"),
+					Reason::Part(Some(Arc::new(part.clone())))
+				)))
+			);
+		}
+	}
+
+	mod fast_paths {
+		use super::*;
+		use sked::ExceptionPrecedence;
+
+		/// A space open on Thursday from 07:00 to 17:00, whose schedule takes
+		/// effect at noon on 2020-01-16 while the part is open
+		fn generate_space_effective_mid_day() -> Space<FixedOffset> {
+			let part = Part::new().open(thursday(7, 0)).close(thursday(17, 0));
+
+			let mut schedule = Schedule::new().part(part);
+			*schedule.effective_mut() = Some(at("2020-01-16T12:00:00-06:00"));
+
+			Space::new("asdf").schedule(schedule)
+		}
+
+		/// A space open on Thursday from 07:00 to 17:00, which was open until 16:00
+		/// under a schedule that expired in 2019, along with its part's close
+		fn generate_space_after_expired_schedule() -> Space<FixedOffset> {
+			let old_part = Part::new()
+				.open(thursday(7, 0))
+				.close(thursday(16, 0).until(at("2019-06-01T00:00:00-05:00")));
+
+			let mut old_schedule = Schedule::new().part(old_part);
+			*old_schedule.effective_mut() = Some(at("2019-01-01T00:00:00-06:00"));
+			*old_schedule.expires_mut() = Some(at("2019-06-01T00:00:00-05:00"));
+
+			let part = Part::new().open(thursday(7, 0)).close(thursday(17, 0));
+
+			let mut schedule = Schedule::new().part(part);
+			*schedule.effective_mut() = Some(at("2020-01-01T00:00:00-06:00"));
+
+			Space::new("asdf").schedule(old_schedule).schedule(schedule)
+		}
+
+		#[test]
+		fn match_space() {
+			let scenarios: Vec<(&str, Space<FixedOffset>, &str, &str, Duration)> = vec![
+				(
+					"regular",
+					generate_space("asdf").0,
+					"2019-12-30T00:00:00-06:00",
+					"2020-02-03T00:00:00-06:00",
+					Duration::minutes(5),
+				),
+				(
+					"effective mid-day",
+					generate_space_effective_mid_day(),
+					"2020-01-13T00:00:00-06:00",
+					"2020-01-27T00:00:00-06:00",
+					Duration::minutes(15),
+				),
+				(
+					"after an expired schedule",
+					generate_space_after_expired_schedule(),
+					"2020-01-13T00:00:00-06:00",
+					"2020-01-20T00:00:00-06:00",
+					Duration::minutes(15),
+				),
+				(
+					"break",
+					precedence::generate_space_with_break(0).0,
+					"2020-01-13T00:00:00-06:00",
+					"2020-01-27T00:00:00-06:00",
+					Duration::minutes(15),
+				),
+				(
+					"exception precedence",
+					exception_precedence::generate_space(
+						ExceptionPrecedence::MostRecent,
+						thursday(17, 30),
+						0,
+						0,
+					),
+					"2020-01-13T00:00:00-06:00",
+					"2020-01-20T00:00:00-06:00",
+					Duration::minutes(15),
+				),
+				(
+					"closed until further notice",
+					open_ended_exceptions::generate_space(open_ended_exceptions::closure(
+						Exception::new().effective(Specifier::Exact(at("2019-12-20T00:00:00-06:00"))),
+					)),
+					"2019-12-01T00:00:00-06:00",
+					"2020-02-01T00:00:00-06:00",
+					Duration::minutes(30),
+				),
+				(
+					"closed until a date",
+					open_ended_exceptions::generate_space(open_ended_exceptions::closure(
+						Exception::new().expires(Specifier::Exact(at("2020-01-03T00:00:00-06:00"))),
+					)),
+					"2019-12-01T00:00:00-06:00",
+					"2020-02-01T00:00:00-06:00",
+					Duration::minutes(30),
+				),
+				(
+					"closed during a range",
+					open_ended_exceptions::generate_space(open_ended_exceptions::closure(Exception::during(
+						at("2019-12-24T00:00:00-06:00"),
+						at("2019-12-27T00:00:00-06:00"),
+					))),
+					"2019-12-01T00:00:00-06:00",
+					"2020-02-01T00:00:00-06:00",
+					Duration::minutes(30),
+				),
+				(
+					"scoped exceptions with competing targets",
+					scoped_exceptions::generate_space(vec![
+						scoped_exceptions::reopening(),
+						scoped_exceptions::on_the_16th().target("help desk"),
+					])
+					.exception_precedence(ExceptionPrecedence::ClosuresFirst),
+					"2020-01-01T00:00:00-06:00",
+					"2020-02-01T00:00:00-06:00",
+					Duration::minutes(30),
+				),
+				(
+					"scoped exception",
+					scoped_exceptions::generate_space(vec![
+						scoped_exceptions::on_the_16th().target("help desk")
+					]),
+					"2020-01-01T00:00:00-06:00",
+					"2020-02-01T00:00:00-06:00",
+					Duration::minutes(30),
+				),
+			];
+
+			for (scenario, space, from, to, step) in scenarios.iter() {
+				assert_fast_paths_match(scenario, space, &at(from), &at(to), *step);
+			}
+		}
+	}
+
+	mod errors {
+		use super::*;
+		use sked::{Rule, RuleError, StatusError};
+
+		/// A space whose second schedule has a part which stops closing after
+		/// 2020-01-10
+		fn generate_broken_space() -> Space<FixedOffset> {
+			let part = Part::new()
+				.open(Specifier::Daily {
+					time: NaiveTime::from_hms(7, 0, 0),
+				})
+				.close(
+					Specifier::Daily {
+						time: NaiveTime::from_hms(17, 0, 0),
+					}
+					.until(at("2020-01-10T00:00:00-06:00")),
+				);
+
+			let mut expired: Schedule<FixedOffset> = Schedule::new();
+			*expired.expires_mut() = Some(at("2020-01-01T00:00:00-06:00"));
+
+			Space::new("Library")
+				.schedule(expired)
+				.schedule(Schedule::new().part(part))
+		}
+
+		fn broken_part() -> StatusError {
+			StatusError {
+				space: "Library".to_string(),
+				schedule: 1,
+				rule: Rule::Part(0),
+				error: RuleError::NoInstance("close"),
+			}
+		}
+
+		#[test]
+		fn part_which_never_closes() {
+			let space = generate_broken_space();
+
+			assert!(space
+				.status_at(&at("2020-01-08T08:00:00-06:00"))
+				.unwrap()
+				.is_open());
+			assert_eq!(
+				space.status_at(&at("2020-01-16T08:00:00-06:00")),
+				Err(broken_part())
+			);
+		}
+
+		#[test]
+		fn exception_without_instance() {
+			let mut exception = Exception::new()
+				.effective(Specifier::Weekly {
+					day: Weekday::Thu,
+					time: NaiveTime::from_hms(10, 15, 0),
+				})
+				.expires(
+					Specifier::Weekly {
+						day: Weekday::Thu,
+						time: NaiveTime::from_hms(11, 0, 0),
+					}
+					.until(at("2020-01-01T00:00:00-06:00")),
+				);
+			*exception.effect_mut() = Some(Status::Closed(Reason::Exception(None)));
+
+			let space: Space<FixedOffset> =
+				Space::new("Library").schedule(Schedule::new().exception(exception));
+			let error = StatusError {
+				space: "Library".to_string(),
+				schedule: 0,
+				rule: Rule::Exception(0),
+				error: RuleError::NoInstance("expires"),
+			};
+
+			assert_eq!(
+				space.status_at(&at("2020-01-16T08:00:00-06:00")),
+				Err(error.clone())
+			);
+			assert_eq!(
+				space
+					.compile(
+						&at("2020-01-13T00:00:00-06:00"),
+						&at("2020-01-20T00:00:00-06:00")
+					)
+					.err(),
+				Some(error)
+			);
+		}
+
+		#[test]
+		fn timeline_stops_after_error() {
+			let space = generate_broken_space();
+			let timeline: Vec<_> = space
+				.timeline(
+					&at("2020-01-08T00:00:00-06:00"),
+					&at("2020-01-17T00:00:00-06:00"),
+				)
+				.collect();
+
+			assert_eq!(timeline.last(), Some(&Err(broken_part())));
+			assert!(timeline[..timeline.len() - 1].iter().all(Result::is_ok));
+		}
+
+		#[test]
+		fn compile_reports_error() {
+			let space = generate_broken_space();
+
+			assert_eq!(
+				space
+					.compile(
+						&at("2020-01-13T00:00:00-06:00"),
+						&at("2020-01-20T00:00:00-06:00")
+					)
+					.err(),
+				Some(broken_part())
+			);
+		}
+
+		#[test]
+		fn error_message_names_location() {
+			assert_eq!(
+				broken_part().to_string(),
+				"space \"Library\", schedule 1, part 0: close specifier has no instance"
+			);
+		}
+	}
+
+	mod clock {
+		use super::*;
+		use sked::{Clock, ManualClock, SystemClock};
+
+		#[test]
+		fn status_follows_manual_clock() {
+			let (space, part) = generate_space("asdf");
+			let clock = ManualClock::new(at("2020-01-16T08:00:00-06:00"));
+
+			assert_eq!(
+				space.status(&clock),
+				Ok(Status::Open(Reason::Part(Some(Arc::new(part)))))
+			);
+
+			clock.advance(Duration::hours(2) + Duration::minutes(30));
+			assert_eq!(
+				space.status(&clock),
+				Ok(Status::Closed(Reason::Exception(Some(
+					"Closed for lunch.".to_string()
+				))))
+			);
+
+			clock.set(at("2020-01-16T18:00:00-06:00"));
+			assert_eq!(clock.now(), at("2020-01-16T18:00:00-06:00"));
+			assert!(!space.status(&clock).unwrap().is_open());
+		}
+
+		#[test]
+		fn next_status_change_follows_manual_clock() {
+			let (space, _) = generate_space("asdf");
+			let clock = ManualClock::new(at("2020-01-16T11:00:00-06:00"));
+
+			assert_eq!(
+				space.next_status_change(&clock),
+				Ok(Some(StatusChange::Closing(
+					at("2020-01-16T17:00:00-06:00"),
+					Reason::Part(None)
+				)))
+			);
+		}
+
+		#[test]
+		fn system_clock_works_in_any_time_zone() {
+			let space = Space::new("asdf").schedule(Schedule::new());
+			let clock = SystemClock::new(chrono_tz::America::Chicago);
+
+			assert!(
+				clock
+					.now()
+					.signed_duration_since(chrono::Utc::now())
+					.num_seconds()
+					.abs()
+					< 5
+			);
+			assert_eq!(space.status(&clock), Ok(Status::Closed(Reason::Part(None))));
+		}
+	}
+
+	mod explain {
+		use super::*;
+		use sked::{Activity, Explanation, Rule, RuleTrace, ScheduleTrace};
+
+		#[test]
+		fn exception_decides_during_lunch() {
+			let (space, _) = generate_space("Library");
+			let time = at("2020-01-16T10:30:00-06:00");
+
+			assert_eq!(
+				space.explain_at(&time),
+				Ok(Explanation {
+					space: "Library".to_string(),
+					time,
+					schedules: vec![ScheduleTrace {
+						activity: Activity::Active,
+						parts: vec![RuleTrace {
+							applies: true,
+							start: Some(at("2020-01-16T07:00:00-06:00")),
+							end: Some(at("2020-01-16T17:00:00-06:00")),
+						}],
+						exceptions: vec![RuleTrace {
+							applies: true,
+							start: Some(at("2020-01-16T10:15:00-06:00")),
+							end: Some(at("2020-01-16T11:00:00-06:00")),
+						}],
+					}],
+					schedule: Some(0),
+					decided_by: Some((0, Rule::Exception(0))),
+					conflicts: vec![],
+					status: Status::Closed(Reason::Exception(Some("Closed for lunch.".to_string()))),
+				})
+			);
+		}
+
+		#[test]
+		fn part_decides_while_open() {
+			let (space, part) = generate_space("Library");
+			let explanation = space.explain_at(&at("2020-01-16T08:00:00-06:00")).unwrap();
+
+			assert_eq!(explanation.decided_by, Some((0, Rule::Part(0))));
+			assert!(!explanation.schedules[0].exceptions[0].applies);
+			assert_eq!(
+				explanation.status,
+				Status::Open(Reason::Part(Some(Arc::new(part))))
+			);
+		}
+
+		#[test]
+		fn expired_schedule_is_not_evaluated() {
+			let (space, _) = generate_space("Library");
+			let explanation = space.explain_at(&at("2020-02-06T08:00:00-06:00")).unwrap();
+
+			assert_eq!(
+				explanation.schedules,
+				vec![ScheduleTrace {
+					activity: Activity::Expired,
+					parts: vec![],
+					exceptions: vec![],
+				}]
+			);
+			assert_eq!(explanation.decided_by, None);
+			assert_eq!(explanation.status, Status::Closed(Reason::Part(None)));
+		}
+
+		#[test]
+		fn displays_trace() {
+			let (space, _) = generate_space("Library");
+
+			assert_eq!(
+				space
+					.explain_at(&at("2020-01-16T10:30:00-06:00"))
+					.unwrap()
+					.to_string(),
+				[
+					"\"Library\" is closed at 2020-01-16 10:30:00 -06:00: Closed for lunch.",
+					"  schedule 0 is active",
+					"    part 0 applies (from 2020-01-16 07:00:00 -06:00 until 2020-01-16 17:00:00 -06:00)",
+					"    exception 0 applies (from 2020-01-16 10:15:00 -06:00 until 2020-01-16 11:00:00 -06:00)",
+					"decided by schedule 0, exception 0",
+				]
+				.join("\n")
+			);
+		}
+	}
+
+	mod precedence {
+		use super::*;
+		use sked::Activity;
+
+		/// The regular schedule, with a break from 2020-01-15 until 2020-01-18
+		/// during which the space is only open on Thursday from 10:00 to 12:00
+		pub(super) fn generate_space_with_break(
+			priority: i32,
+		) -> (Space<FixedOffset>, Part<FixedOffset>, Part<FixedOffset>) {
+			let (space, main) = generate_space("asdf");
+
+			let part = Part::new()
+				.open(Specifier::Weekly {
+					day: Weekday::Thu,
+					time: NaiveTime::from_hms(10, 0, 0),
+				})
+				.close(Specifier::Weekly {
+					day: Weekday::Thu,
+					time: NaiveTime::from_hms(12, 0, 0),
+				});
+
+			let mut schedule: Schedule<FixedOffset> = Schedule::new().part(part.clone());
+			*schedule.effective_mut() = Some(at("2020-01-15T00:00:00-06:00"));
+			*schedule.expires_mut() = Some(at("2020-01-18T00:00:00-06:00"));
+			*schedule.priority_mut() = priority;
+
+			(space.schedule(schedule), main, part)
+		}
+
+		#[test]
+		fn narrowest_schedule_wins() {
+			let (space, _, part) = generate_space_with_break(0);
+
+			assert_eq!(
+				space.status_at(&at("2020-01-16T08:00:00-06:00")),
+				Ok(Status::Closed(Reason::Part(None)))
+			);
+			assert_eq!(
+				space.status_at(&at("2020-01-16T10:30:00-06:00")),
+				Ok(Status::Open(Reason::Part(Some(Arc::new(part)))))
+			);
+			assert_eq!(
+				space.status_at(&at("2020-01-16T15:00:00-06:00")),
+				Ok(Status::Closed(Reason::Part(None)))
+			);
+		}
+
+		#[test]
+		fn regular_schedule_applies_outside_break() {
+			let (space, main, _) = generate_space_with_break(0);
+
+			assert_eq!(
+				space.status_at(&at("2020-01-23T08:00:00-06:00")),
+				Ok(Status::Open(Reason::Part(Some(Arc::new(main)))))
+			);
+		}
+
+		#[test]
+		fn priority_overrides_window() {
+			let (space, main, _) = generate_space_with_break(-1);
+
+			assert_eq!(
+				space.status_at(&at("2020-01-16T08:00:00-06:00")),
+				Ok(Status::Open(Reason::Part(Some(Arc::new(main)))))
+			);
+		}
+
+		#[test]
+		fn exceptions_of_superseded_schedule_are_ignored() {
+			let part = Part::new().open(thursday(7, 0)).close(thursday(17, 0));
+			let mut closure = Exception::during(
+				at("2020-01-15T00:00:00-06:00"),
+				at("2020-01-17T00:00:00-06:00"),
+			);
+			*closure.effect_mut() = Some(Status::Closed(Reason::Exception(None)));
+
+			let break_part = Part::new().open(thursday(10, 0)).close(thursday(12, 0));
+			let mut break_schedule: Schedule<FixedOffset> = Schedule::new().part(break_part.clone());
+			*break_schedule.effective_mut() = Some(at("2020-01-15T00:00:00-06:00"));
+			*break_schedule.expires_mut() = Some(at("2020-01-18T00:00:00-06:00"));
+
+			let space = Space::new("asdf")
+				.schedule(Schedule::new().part(part).exception(closure))
+				.schedule(break_schedule);
+
+			assert_eq!(
+				space.status_at(&at("2020-01-16T10:30:00-06:00")),
+				Ok(Status::Open(Reason::Part(Some(Arc::new(break_part)))))
+			);
+		}
+
+		#[test]
+		fn explanation_reports_chosen_schedule() {
+			let (space, _, _) = generate_space_with_break(0);
+			let explanation = space.explain_at(&at("2020-01-16T08:00:00-06:00")).unwrap();
+
+			assert_eq!(explanation.schedule, Some(1));
+			assert_eq!(explanation.schedules[0].activity, Activity::Superseded);
+			assert_eq!(explanation.schedules[1].activity, Activity::Active);
+		}
+	}
+
+	mod exception_precedence {
+		use super::*;
+		use sked::{Conflict, ExceptionPrecedence, Rule};
+
+		/// A space which is normally open on Thursday from 07:00 to 17:00, with
+		/// extended hours for finals until 23:00 and a campus-wide closure from
+		/// the given time, each with the given priority
+		pub(super) fn generate_space(
+			precedence: ExceptionPrecedence,
+			closure_from: Specifier<FixedOffset>,
+			finals_priority: i32,
+			closure_priority: i32,
+		) -> Space<FixedOffset> {
+			let part = Part::new().open(thursday(7, 0)).close(thursday(17, 0));
+
+			let mut finals = Exception::new()
+				.effective(thursday(17, 0))
+				.expires(thursday(23, 0));
+			*finals.effect_mut() = Some(Status::Open(Reason::Exception(Some(
+				"Extended hours for finals.".to_string(),
+			))));
+			*finals.priority_mut() = finals_priority;
+
+			let mut closure = Exception::new()
+				.effective(closure_from)
+				.expires(thursday(23, 59));
+			*closure.effect_mut() = Some(Status::Closed(Reason::Exception(Some(
+				"Campus closed.".to_string(),
+			))));
+			*closure.priority_mut() = closure_priority;
+
+			Space::new("asdf")
+				.exception_precedence(precedence)
+				.schedule(
+					Schedule::new()
+						.part(part)
+						.exception(finals)
+						.exception(closure),
+				)
+		}
+
+		fn status_at_18(space: &Space<FixedOffset>) -> Status<FixedOffset> {
+			space.status_at(&at("2020-01-16T18:00:00-06:00")).unwrap()
+		}
+
+		#[test]
+		fn first_added_wins_by_default() {
+			let space = generate_space(ExceptionPrecedence::default(), thursday(16, 0), 0, 0);
+			assert!(status_at_18(&space).is_open());
+		}
+
+		#[test]
+		fn priority_wins() {
+			let space = generate_space(ExceptionPrecedence::FirstAdded, thursday(16, 0), 0, 1);
+			assert_eq!(
+				status_at_18(&space),
+				Status::Closed(Reason::Exception(Some("Campus closed.".to_string())))
+			);
+		}
+
+		#[test]
+		fn priority_wins_over_precedence() {
+			let space = generate_space(ExceptionPrecedence::ClosuresFirst, thursday(16, 0), 1, 0);
+			assert!(status_at_18(&space).is_open());
+		}
+
+		#[test]
+		fn closures_first() {
+			let space = generate_space(ExceptionPrecedence::ClosuresFirst, thursday(16, 0), 0, 0);
+			assert!(!status_at_18(&space).is_open());
+		}
+
+		#[test]
+		fn most_recent_wins() {
+			let space = generate_space(ExceptionPrecedence::MostRecent, thursday(16, 0), 0, 0);
+			assert!(status_at_18(&space).is_open());
+
+			let space = generate_space(ExceptionPrecedence::MostRecent, thursday(17, 30), 0, 0);
+			assert!(!status_at_18(&space).is_open());
+		}
+
+		#[test]
+		fn explanation_reports_conflicts() {
+			let space = generate_space(ExceptionPrecedence::ClosuresFirst, thursday(16, 0), 0, 0);
+			let explanation = space.explain_at(&at("2020-01-16T18:00:00-06:00")).unwrap();
+
+			assert_eq!(explanation.decided_by, Some((0, Rule::Exception(1))));
+			assert_eq!(explanation.conflicts, vec![(0, Rule::Exception(0))]);
+		}
+
+		#[test]
+		fn conflicts_over_a_range() {
+			let space = generate_space(ExceptionPrecedence::ClosuresFirst, thursday(16, 0), 0, 0);
+
+			assert_eq!(
+				space.conflicts(
+					&at("2020-01-13T00:00:00-06:00"),
+					&at("2020-01-27T00:00:00-06:00")
+				),
+				Ok(vec![
+					Conflict {
+						start: at("2020-01-16T17:00:00-06:00"),
+						end: at("2020-01-16T23:00:00-06:00"),
+						decided_by: Some((0, Rule::Exception(1))),
+						overridden: vec![(0, Rule::Exception(0))],
+					},
+					Conflict {
+						start: at("2020-01-23T17:00:00-06:00"),
+						end: at("2020-01-23T23:00:00-06:00"),
+						decided_by: Some((0, Rule::Exception(1))),
+						overridden: vec![(0, Rule::Exception(0))],
+					},
+				])
+			);
+		}
+
+		#[test]
+		fn no_conflicts_without_overlap() {
+			let space = generate_space(ExceptionPrecedence::ClosuresFirst, thursday(23, 0), 0, 0);
+
+			assert_eq!(
+				space.conflicts(
+					&at("2020-01-13T00:00:00-06:00"),
+					&at("2020-01-27T00:00:00-06:00")
+				),
+				Ok(vec![])
+			);
+		}
+	}
+
+	mod open_ended_exceptions {
+		use super::*;
+
+		/// A space which is open every Thursday from 07:00 to 17:00, with no
+		/// bounds on its schedule, and the given exception
+		pub(super) fn generate_space(exception: Exception<FixedOffset>) -> Space<FixedOffset> {
+			let part = Part::new().open(thursday(7, 0)).close(thursday(17, 0));
+
+			Space::new("asdf").schedule(Schedule::new().part(part).exception(exception))
+		}
+
+		pub(super) fn closure(exception: Exception<FixedOffset>) -> Exception<FixedOffset> {
+			let mut exception = exception;
+			*exception.effect_mut() = Some(Status::Closed(Reason::Exception(None)));
+			exception
+		}
+
+		fn is_open_at(space: &Space<FixedOffset>, time: &str) -> bool {
+			space.status_at(&at(time)).unwrap().is_open()
+		}
+
+		#[test]
+		fn closed_until_further_notice() {
+			let space = generate_space(closure(
+				Exception::new().effective(Specifier::Exact(at("2019-12-20T00:00:00-06:00"))),
+			));
+
+			assert!(is_open_at(&space, "2019-12-12T12:00:00-06:00"));
+			assert!(is_open_at(&space, "2019-12-19T12:00:00-06:00"));
+			assert!(!is_open_at(&space, "2019-12-26T12:00:00-06:00"));
+			assert!(!is_open_at(&space, "2020-06-18T12:00:00-06:00"));
+			assert!(!is_open_at(&space, "2029-06-21T12:00:00-05:00"));
+		}
+
+		#[test]
+		fn closed_until_a_date() {
+			let space = generate_space(closure(
+				Exception::new().expires(Specifier::Exact(at("2020-01-03T00:00:00-06:00"))),
+			));
+
+			assert!(!is_open_at(&space, "2019-06-13T12:00:00-06:00"));
+			assert!(!is_open_at(&space, "2020-01-02T12:00:00-06:00"));
+			assert!(is_open_at(&space, "2020-01-09T12:00:00-06:00"));
+		}
+
+		#[test]
+		fn closed_during_a_range() {
+			let space = generate_space(closure(Exception::during(
+				at("2019-12-24T00:00:00-06:00"),
+				at("2019-12-27T00:00:00-06:00"),
+			)));
+
+			assert!(is_open_at(&space, "2019-12-19T12:00:00-06:00"));
+			assert!(!is_open_at(&space, "2019-12-26T12:00:00-06:00"));
+			assert!(is_open_at(&space, "2020-01-02T12:00:00-06:00"));
+		}
+
+		#[test]
+		fn exception_past_midnight() {
+			let mut exception = Exception::new()
+				.effective(thursday(22, 0))
+				.expires(Specifier::Weekly {
+					day: Weekday::Fri,
+					time: NaiveTime::from_hms(2, 0, 0),
+				});
+			*exception.effect_mut() = Some(Status::Open(Reason::Exception(None)));
+			let space = generate_space(exception);
+
+			assert!(!is_open_at(&space, "2020-01-16T21:59:59-06:00"));
+			assert!(is_open_at(&space, "2020-01-16T23:00:00-06:00"));
+			assert!(is_open_at(&space, "2020-01-17T01:00:00-06:00"));
+			assert!(!is_open_at(&space, "2020-01-17T02:00:00-06:00"));
+		}
+
+		#[test]
+		fn explanation_shows_open_bound() {
+			let space = generate_space(closure(
+				Exception::new().effective(Specifier::Exact(at("2019-12-20T00:00:00-06:00"))),
+			));
+			let explanation = space.explain_at(&at("2019-12-26T12:00:00-06:00")).unwrap();
+
+			assert_eq!(
+				explanation.schedules[0].exceptions[0].start,
+				Some(at("2019-12-20T00:00:00-06:00"))
+			);
+			assert_eq!(explanation.schedules[0].exceptions[0].end, None);
+		}
+	}
+
+	mod scoped_exceptions {
+		use super::*;
+		use sked::{ExceptionPrecedence, Rule};
+
+		fn help_desk() -> Part<FixedOffset> {
+			Part::new()
+				.open(thursday(9, 0))
+				.close(thursday(12, 0))
+				.label("help desk")
+		}
+
+		fn building() -> Part<FixedOffset> {
+			Part::new()
+				.open(thursday(7, 0))
+				.close(thursday(17, 0))
+				.label("building")
+		}
+
+		/// A space whose help desk is open on Thursday from 09:00 to 12:00, in a
+		/// building open from 07:00 to 17:00, with the given exceptions
+		pub(super) fn generate_space(exceptions: Vec<Exception<FixedOffset>>) -> Space<FixedOffset> {
+			let mut schedule = Schedule::new().part(help_desk()).part(building());
+			schedule.exceptions_mut().extend(exceptions);
+
+			Space::new("asdf").schedule(schedule)
+		}
+
+		pub(super) fn on_the_16th() -> Exception<FixedOffset> {
+			Exception::during(
+				at("2020-01-16T00:00:00-06:00"),
+				at("2020-01-17T00:00:00-06:00"),
+			)
+		}
+
+		#[test]
+		fn closing_one_part_reduces_service() {
+			let space = generate_space(vec![on_the_16th().target("help desk")]);
+			let status = space.status_at(&at("2020-01-16T10:00:00-06:00")).unwrap();
+
+			assert!(status.is_reduced());
+			assert_eq!(
+				status,
+				Status::Open(Reason::Reduced {
+					part: Arc::new(building()),
+					closed: vec![Arc::new(help_desk())],
+				})
+			);
+		}
+
+		#[test]
+		fn service_is_not_reduced_when_the_part_is_closed_anyway() {
+			let space = generate_space(vec![on_the_16th().target("help desk")]);
+
+			assert_eq!(
+				space.status_at(&at("2020-01-16T13:00:00-06:00")),
+				Ok(Status::Open(Reason::Part(Some(Arc::new(building())))))
+			);
+			assert_eq!(
+				space.status_at(&at("2020-01-09T10:00:00-06:00")),
+				Ok(Status::Open(Reason::Part(Some(Arc::new(help_desk())))))
+			);
+		}
+
+		#[test]
+		fn closing_every_part_closes_the_space() {
+			let mut exception = on_the_16th().target("help desk").target("building");
+			*exception.effect_mut() = Some(Status::Closed(Reason::Exception(Some(
+				"Closed for repairs.".to_string(),
+			))));
+			let space = generate_space(vec![exception]);
+			let explanation = space.explain_at(&at("2020-01-16T10:00:00-06:00")).unwrap();
+
+			assert_eq!(
+				explanation.status,
+				Status::Closed(Reason::Exception(Some("Closed for repairs.".to_string())))
+			);
+			assert_eq!(explanation.decided_by, Some((0, Rule::Exception(0))));
+		}
+
+		#[test]
+		fn whole_space_exception_still_decides() {
+			let mut closure = on_the_16th();
+			*closure.effect_mut() = Some(Status::Closed(Reason::Exception(None)));
+			let space = generate_space(vec![on_the_16th().target("help desk"), closure]);
+
+			assert_eq!(
+				space.status_at(&at("2020-01-16T10:00:00-06:00")),
+				Ok(Status::Closed(Reason::Exception(None)))
+			);
+		}
+
+		/// An exception which reopens the help desk on the 16th
+		pub(super) fn reopening() -> Exception<FixedOffset> {
+			let mut exception = on_the_16th().target("help desk");
+			*exception.effect_mut() = Some(Status::Open(Reason::Exception(None)));
+			exception
+		}
+
+		#[test]
+		fn opening_effect_opens_the_part() {
+			let mut extended = Exception::during(
+				at("2020-01-16T12:00:00-06:00"),
+				at("2020-01-16T19:00:00-06:00"),
+			)
+			.target("help desk");
+			*extended.effect_mut() = Some(Status::Open(Reason::Exception(Some(
+				"Extended desk hours.".to_string(),
+			))));
+			let space = generate_space(vec![extended]);
+
+			for time in &["2020-01-16T13:00:00-06:00", "2020-01-16T18:00:00-06:00"] {
+				let explanation = space.explain_at(&at(time)).unwrap();
+
+				assert_eq!(
+					explanation.status,
+					Status::Open(Reason::Part(Some(Arc::new(help_desk()))))
+				);
+				assert_eq!(explanation.decided_by, Some((0, Rule::Exception(0))));
+			}
+			assert!(!space
+				.status_at(&at("2020-01-16T19:30:00-06:00"))
+				.unwrap()
+				.is_open());
+		}
+
+		#[test]
+		fn priority_decides_between_targeting_exceptions() {
+			let mut closure = on_the_16th().target("help desk");
+			*closure.priority_mut() = 0;
+			let mut reopening = reopening();
+			*reopening.priority_mut() = 1;
+			let space = generate_space(vec![closure, reopening]);
+			let explanation = space.explain_at(&at("2020-01-16T10:00:00-06:00")).unwrap();
+
+			assert_eq!(
+				explanation.status,
+				Status::Open(Reason::Part(Some(Arc::new(help_desk()))))
+			);
+			assert_eq!(explanation.decided_by, Some((0, Rule::Exception(1))));
+			assert_eq!(explanation.conflicts, vec![(0, Rule::Exception(0))]);
+		}
+
+		#[test]
+		fn first_added_targeting_exception_wins_by_default() {
+			let space = generate_space(vec![on_the_16th().target("help desk"), reopening()]);
+			assert!(space
+				.status_at(&at("2020-01-16T10:00:00-06:00"))
+				.unwrap()
+				.is_reduced());
+		}
+
+		#[test]
+		fn precedence_decides_between_targeting_exceptions() {
+			let space = generate_space(vec![reopening(), on_the_16th().target("help desk")])
+				.exception_precedence(ExceptionPrecedence::ClosuresFirst);
+			assert!(space
+				.status_at(&at("2020-01-16T10:00:00-06:00"))
+				.unwrap()
+				.is_reduced());
+		}
+
+		#[test]
+		fn explanation_mentions_reduced_service() {
+			let space = generate_space(vec![on_the_16th().target("help desk")]);
+			let explanation = space.explain_at(&at("2020-01-16T10:00:00-06:00")).unwrap();
+
+			assert_eq!(explanation.decided_by, Some((0, Rule::Part(1))));
+			assert!(explanation
+				.to_string()
+				.starts_with("\"asdf\" is open at 2020-01-16 10:00:00 -06:00 with reduced service\n"));
+		}
+	}
+
+	mod threads {
+		use super::*;
+		use std::thread;
+
+		#[test]
+		fn space_is_shared_between_threads() {
+			let (space, part) = generate_space("asdf");
+			let space = Arc::new(space);
+
+			let status = {
+				let space = Arc::clone(&space);
+				thread::spawn(move || {
+					space.status_at(&DateTime::parse_from_rfc3339("2020-01-16T08:00:00-06:00").unwrap())
+				})
+				.join()
+				.unwrap()
+			};
+
+			assert_eq!(status, Ok(Status::Open(Reason::Part(Some(Arc::new(part))))));
+		}
+	}
 }
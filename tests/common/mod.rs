@@ -0,0 +1,56 @@
+use chrono::{DateTime, Duration, FixedOffset};
+use sked::Space;
+
+pub fn at(time: &str) -> DateTime<FixedOffset> {
+	DateTime::parse_from_rfc3339(time).unwrap()
+}
+
+/// Check that a compiled space and a status index over the window from `from`
+/// to `to` agree with [`Space::status_at`] at every `step`, from 12 hours
+/// before the window until 12 hours after it
+///
+/// The index is also checked against [`Space::next_status_change_at`] at the
+/// first step of each stretch with the same status, since it gives the same
+/// answer throughout a stretch.
+pub fn assert_fast_paths_match(
+	scenario: &str,
+	space: &Space<FixedOffset>,
+	from: &DateTime<FixedOffset>,
+	to: &DateTime<FixedOffset>,
+	step: Duration,
+) {
+	let compiled = space.compile(from, to).unwrap();
+	let index = space.index(from, to).unwrap();
+
+	let mut previous = None;
+	let mut time = *from - Duration::hours(12);
+	while time < *to + Duration::hours(12) {
+		let expected = space.status_at(&time);
+		assert_eq!(
+			compiled.status_at(&time),
+			expected,
+			"{}: compiled at {}",
+			scenario,
+			time
+		);
+		assert_eq!(
+			index.status_at(&time),
+			expected,
+			"{}: index at {}",
+			scenario,
+			time
+		);
+		if previous.as_ref() != Some(&expected) {
+			assert_eq!(
+				index.next_status_change_at(&time),
+				space.next_status_change_at(&time),
+				"{}: next change from index at {}",
+				scenario,
+				time
+			);
+		}
+
+		previous = Some(expected);
+		time = time + step;
+	}
+}
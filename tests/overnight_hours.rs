@@ -0,0 +1,410 @@
+use chrono::{DateTime, Duration, FixedOffset, NaiveTime, Weekday};
+use sked::{Part, Reason, Schedule, Space, Specifier, Status, StatusChange};
+use std::sync::Arc;
+
+mod common;
+use common::{assert_fast_paths_match, at};
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	macro_rules! check_space_at_time {
+		($test_name:ident, $time:literal, $var:ident, $expected:expr) => {
+			#[test]
+			fn $test_name() {
+				let (space, $var): (Space<FixedOffset>, Part<FixedOffset>) = generate_space("asdf");
+				let time: DateTime<FixedOffset> = DateTime::parse_from_rfc3339($time).unwrap();
+				assert_eq!(space.status_at(&time), Ok($expected));
+			}
+		};
+		($test_name:ident, $time:literal, $expected:expr) => {
+			check_space_at_time!($test_name, $time, __nil__, $expected);
+		};
+	}
+
+	/// A space which is open late on Friday and Sunday nights, into the early
+	/// hours of the following day
+	fn generate_space(name: &str) -> (Space<FixedOffset>, Part<FixedOffset>) {
+		let friday = Part::new()
+			.open(Specifier::Weekly {
+				day: Weekday::Fri,
+				time: NaiveTime::from_hms(22, 0, 0),
+			})
+			.close(Specifier::Weekly {
+				day: Weekday::Sat,
+				time: NaiveTime::from_hms(2, 0, 0),
+			});
+
+		let sunday = Part::new()
+			.open(Specifier::Weekly {
+				day: Weekday::Sun,
+				time: NaiveTime::from_hms(22, 0, 0),
+			})
+			.close(Specifier::Weekly {
+				day: Weekday::Mon,
+				time: NaiveTime::from_hms(2, 0, 0),
+			});
+
+		let schedule: Schedule<FixedOffset> = Schedule::new().part(friday.clone()).part(sunday);
+
+		(Space::new(name).schedule(schedule), friday)
+	}
+
+	check_space_at_time!(
+		before_friday_open_is_closed,
+		"2020-01-17T21:59:59-06:00",
+		Status::Closed(Reason::Part(None))
+	);
+
+	check_space_at_time!(
+		friday_before_midnight_is_open,
+		"2020-01-17T23:00:00-06:00",
+		__friday__,
+		Status::Open(Reason::Part(Some(Arc::new(__friday__.clone()))))
+	);
+
+	check_space_at_time!(
+		saturday_after_midnight_is_open,
+		"2020-01-18T01:00:00-06:00",
+		__friday__,
+		Status::Open(Reason::Part(Some(Arc::new(__friday__.clone()))))
+	);
+
+	check_space_at_time!(
+		saturday_at_close_is_closed,
+		"2020-01-18T02:00:00-06:00",
+		Status::Closed(Reason::Part(None))
+	);
+
+	check_space_at_time!(
+		saturday_night_is_closed,
+		"2020-01-18T23:00:00-06:00",
+		Status::Closed(Reason::Part(None))
+	);
+
+	mod across_week_boundary {
+		use super::*;
+
+		#[test]
+		fn sunday_before_midnight_is_open() {
+			let (space, _) = generate_space("asdf");
+			let time = DateTime::parse_from_rfc3339("2020-01-19T23:00:00-06:00").unwrap();
+			assert!(space.status_at(&time).unwrap().is_open());
+		}
+
+		#[test]
+		fn monday_after_midnight_is_open() {
+			let (space, _) = generate_space("asdf");
+			let time = DateTime::parse_from_rfc3339("2020-01-20T01:59:59-06:00").unwrap();
+			assert!(space.status_at(&time).unwrap().is_open());
+		}
+
+		#[test]
+		fn monday_at_close_is_closed() {
+			let (space, _) = generate_space("asdf");
+			let time = DateTime::parse_from_rfc3339("2020-01-20T02:00:00-06:00").unwrap();
+			assert!(!space.status_at(&time).unwrap().is_open());
+		}
+	}
+
+	#[test]
+	fn daily_overnight_part() {
+		let part: Part<FixedOffset> = Part::new()
+			.open(Specifier::Daily {
+				time: NaiveTime::from_hms(22, 0, 0),
+			})
+			.close(Specifier::Daily {
+				time: NaiveTime::from_hms(2, 0, 0),
+			});
+
+		assert!(!part.applies_at(&at("2020-01-16T21:00:00-06:00")).unwrap());
+		assert!(part.applies_at(&at("2020-01-16T23:00:00-06:00")).unwrap());
+		assert!(part.applies_at(&at("2020-01-17T01:00:00-06:00")).unwrap());
+		assert!(!part.applies_at(&at("2020-01-17T03:00:00-06:00")).unwrap());
+	}
+
+	#[test]
+	fn next_change_after_midnight_is_closing() {
+		let (space, _) = generate_space("asdf");
+		let time = DateTime::parse_from_rfc3339("2020-01-17T23:00:00-06:00").unwrap();
+
+		assert_eq!(
+			space.next_status_change_at(&time),
+			Ok(Some(StatusChange::Closing(
+				DateTime::parse_from_rfc3339("2020-01-18T02:00:00-06:00").unwrap(),
+				Reason::Part(None)
+			)))
+		);
+	}
+
+	mod weekday_set {
+		use super::*;
+		use sked::WeekdaySet;
+
+		#[test]
+		fn open_late_on_weekends() {
+			let part: Part<FixedOffset> = Part::new().on_days(
+				"Fri,Sat".parse::<WeekdaySet>().unwrap(),
+				NaiveTime::from_hms(20, 0, 0),
+				NaiveTime::from_hms(2, 0, 0),
+			);
+
+			assert!(!part.applies_at(&at("2020-01-16T23:00:00-06:00")).unwrap());
+			assert!(part.applies_at(&at("2020-01-17T23:00:00-06:00")).unwrap());
+			assert!(part.applies_at(&at("2020-01-18T01:00:00-06:00")).unwrap());
+			assert!(!part.applies_at(&at("2020-01-18T03:00:00-06:00")).unwrap());
+			assert!(part.applies_at(&at("2020-01-19T01:59:59-06:00")).unwrap());
+			assert!(!part.applies_at(&at("2020-01-19T23:00:00-06:00")).unwrap());
+		}
+	}
+
+	mod finals_hours {
+		use super::*;
+		use sked::{Exception, Rule};
+
+		fn daily(hour: u32) -> Specifier<FixedOffset> {
+			Specifier::Daily {
+				time: NaiveTime::from_hms(hour, 0, 0),
+			}
+		}
+
+		/// A library which is open daily from 07:00 to 23:00, except during finals
+		/// from 2020-01-13 until 2020-01-18, when it closes at 02:00 instead
+		pub(super) fn generate_library() -> (Space<FixedOffset>, Part<FixedOffset>) {
+			let regular = Part::new().open(daily(7)).close(daily(23));
+			let extended = Part::new().open(daily(7)).close(daily(2));
+
+			let finals = Exception::new()
+				.effective(Specifier::Exact(at("2020-01-13T00:00:00-06:00")))
+				.expires(Specifier::Exact(at("2020-01-18T00:00:00-06:00")))
+				.part(extended.clone());
+
+			let space = Space::new("Library").schedule(Schedule::new().part(regular).exception(finals));
+
+			(space, extended)
+		}
+
+		#[test]
+		fn open_late_during_finals() {
+			let (space, extended) = generate_library();
+
+			assert_eq!(
+				space.status_at(&at("2020-01-15T23:30:00-06:00")),
+				Ok(Status::Open(Reason::Part(Some(Arc::new(extended.clone())))))
+			);
+			assert_eq!(
+				space.status_at(&at("2020-01-16T01:00:00-06:00")),
+				Ok(Status::Open(Reason::Part(Some(Arc::new(extended)))))
+			);
+			assert_eq!(
+				space.status_at(&at("2020-01-16T03:00:00-06:00")),
+				Ok(Status::Closed(Reason::Part(None)))
+			);
+		}
+
+		#[test]
+		fn regular_hours_outside_finals() {
+			let (space, _) = generate_library();
+
+			assert!(space
+				.status_at(&at("2020-01-20T22:30:00-06:00"))
+				.unwrap()
+				.is_open());
+			assert!(!space
+				.status_at(&at("2020-01-20T23:30:00-06:00"))
+				.unwrap()
+				.is_open());
+		}
+
+		#[test]
+		fn exception_decides_while_closed() {
+			let (space, _) = generate_library();
+			let explanation = space.explain_at(&at("2020-01-16T03:00:00-06:00")).unwrap();
+
+			assert_eq!(explanation.decided_by, Some((0, Rule::Exception(0))));
+		}
+
+		#[test]
+		fn next_change_is_extended_close() {
+			let (space, _) = generate_library();
+
+			assert_eq!(
+				space.next_status_change_at(&at("2020-01-15T22:00:00-06:00")),
+				Ok(Some(StatusChange::Closing(
+					at("2020-01-16T02:00:00-06:00"),
+					Reason::Part(None)
+				)))
+			);
+		}
+
+		#[test]
+		fn timeline_follows_extended_hours() {
+			let (space, extended) = generate_library();
+			let open = Status::Open(Reason::Part(Some(Arc::new(extended))));
+			let closed = Status::Closed(Reason::Part(None));
+
+			assert_eq!(
+				space
+					.timeline(
+						&at("2020-01-15T00:00:00-06:00"),
+						&at("2020-01-16T12:00:00-06:00")
+					)
+					.collect::<Result<Vec<_>, _>>(),
+				Ok(vec![
+					(
+						at("2020-01-15T00:00:00-06:00"),
+						at("2020-01-15T02:00:00-06:00"),
+						open.clone()
+					),
+					(
+						at("2020-01-15T02:00:00-06:00"),
+						at("2020-01-15T07:00:00-06:00"),
+						closed.clone()
+					),
+					(
+						at("2020-01-15T07:00:00-06:00"),
+						at("2020-01-16T02:00:00-06:00"),
+						open.clone()
+					),
+					(
+						at("2020-01-16T02:00:00-06:00"),
+						at("2020-01-16T07:00:00-06:00"),
+						closed
+					),
+					(
+						at("2020-01-16T07:00:00-06:00"),
+						at("2020-01-16T12:00:00-06:00"),
+						open
+					),
+				])
+			);
+		}
+
+		#[test]
+		fn regular_close_the_night_before_finals() {
+			let (space, _) = generate_library();
+
+			for time in ["2020-01-13T00:30:00-06:00", "2020-01-13T01:30:00-06:00"] {
+				assert_eq!(
+					space.status_at(&at(time)),
+					Ok(Status::Closed(Reason::Part(None))),
+					"at {}",
+					time
+				);
+			}
+		}
+
+		#[test]
+		fn extended_close_the_last_night_of_finals() {
+			let (space, extended) = generate_library();
+
+			for time in ["2020-01-18T00:30:00-06:00", "2020-01-18T01:30:00-06:00"] {
+				assert_eq!(
+					space.status_at(&at(time)),
+					Ok(Status::Open(Reason::Part(Some(Arc::new(extended.clone()))))),
+					"at {}",
+					time
+				);
+			}
+			assert_eq!(
+				space.next_status_change_at(&at("2020-01-17T22:00:00-06:00")),
+				Ok(Some(StatusChange::Closing(
+					at("2020-01-18T02:00:00-06:00"),
+					Reason::Part(None)
+				)))
+			);
+		}
+
+		/// A library which is open daily from 07:00 to 01:00, except during finals
+		/// from 2020-01-13 until 2020-01-18, when it is open from 09:00 to midnight
+		/// instead
+		pub(super) fn generate_late_library() -> (Space<FixedOffset>, Part<FixedOffset>) {
+			let regular = Part::new().open(daily(7)).close(daily(1));
+			let finals = Exception::new()
+				.effective(Specifier::Exact(at("2020-01-13T00:00:00-06:00")))
+				.expires(Specifier::Exact(at("2020-01-18T00:00:00-06:00")))
+				.part(Part::new().open(daily(9)).close(daily(0)));
+
+			let space =
+				Space::new("Library").schedule(Schedule::new().part(regular.clone()).exception(finals));
+
+			(space, regular)
+		}
+
+		#[test]
+		fn regular_hours_run_into_finals() {
+			let (space, regular) = generate_late_library();
+
+			assert_eq!(
+				space.status_at(&at("2020-01-13T00:30:00-06:00")),
+				Ok(Status::Open(Reason::Part(Some(Arc::new(regular)))))
+			);
+			assert!(!space
+				.status_at(&at("2020-01-13T01:00:00-06:00"))
+				.unwrap()
+				.is_open());
+		}
+
+		#[test]
+		fn regular_hours_replaced_during_finals() {
+			let (space, _) = generate_late_library();
+			let explanation = space.explain_at(&at("2020-01-13T08:00:00-06:00")).unwrap();
+
+			assert_eq!(explanation.status, Status::Closed(Reason::Part(None)));
+			assert_eq!(explanation.decided_by, Some((0, Rule::Exception(0))));
+			assert!(!space
+				.status_at(&at("2020-01-18T00:30:00-06:00"))
+				.unwrap()
+				.is_open());
+		}
+
+		#[test]
+		fn regular_hours_resume_after_finals() {
+			let (space, regular) = generate_late_library();
+
+			assert_eq!(
+				space.next_status_change_at(&at("2020-01-18T00:30:00-06:00")),
+				Ok(Some(StatusChange::Opening(
+					at("2020-01-18T07:00:00-06:00"),
+					Reason::Part(Some(Arc::new(regular)))
+				)))
+			);
+		}
+	}
+
+	mod fast_paths {
+		use super::*;
+
+		#[test]
+		fn match_space() {
+			let scenarios: Vec<(&str, Space<FixedOffset>, &str, &str, Duration)> = vec![
+				(
+					"overnight",
+					generate_space("asdf").0,
+					"2020-01-13T00:00:00-06:00",
+					"2020-01-27T00:00:00-06:00",
+					Duration::minutes(10),
+				),
+				(
+					"finals",
+					finals_hours::generate_library().0,
+					"2020-01-10T00:00:00-06:00",
+					"2020-01-21T00:00:00-06:00",
+					Duration::minutes(15),
+				),
+				(
+					"late finals",
+					finals_hours::generate_late_library().0,
+					"2020-01-10T00:00:00-06:00",
+					"2020-01-21T00:00:00-06:00",
+					Duration::minutes(15),
+				),
+			];
+
+			for (scenario, space, from, to, step) in scenarios.iter() {
+				assert_fast_paths_match(scenario, space, &at(from), &at(to), *step);
+			}
+		}
+	}
+}